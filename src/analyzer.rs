@@ -1,100 +1,201 @@
-use std::collections::HashSet;
+use std::collections::HashMap;
 
-use choice::PatternElemChoice;
+use builder::{AnalyzerBuilder, OverflowPolicy};
+use choice::{PatternElemChoice, intersection, is_tested};
+use error::AnalyzerError;
+use fuzzy::{FuzzyMatch, Tolerance};
+use index::IndexFormula;
+use iter::PatternIter;
+use linear::LinearRecurrence;
 use meta::MetaAnalyzer;
+use number::Number;
 use pattern::{CustomPatternElem, Pattern, PatternElem};
+use set::PatternSet;
 use stepper::Stepper;
 
 /// Identifies patterns that describe a given sequence.
-pub struct Analyzer {
-    choices: Vec<PatternElemChoice>,
-    meta: bool,
+///
+/// Sequences can be made of any type implementing `Number`, which includes all of the primitive
+/// integer types (and `BigInt`, with the `bigint` feature enabled).
+pub struct Analyzer<T = i32> {
+    seq: Vec<T>,
+    choices: Vec<PatternElemChoice<T>>,
+    options: AnalyzerBuilder<T>,
 }
 
-impl Analyzer {
-    /// Creates a new Analyzer from a slice of integers.
-    pub fn from_slice(seq: &[i32]) -> Self {
+impl<T: Number> Analyzer<T> {
+    /// Creates a new Analyzer from a slice of numbers.
+    pub fn from_slice(seq: &[T]) -> Self {
         Self::with_custom_patterns(seq, Vec::new())
     }
 
     /// Same as `from_slice`, but also finds meta-patterns.
-    pub fn with_meta(seq: &[i32]) -> Self {
+    pub fn with_meta(seq: &[T]) -> Self {
         Self::with_options(seq, true, Vec::new())
     }
 
     /// Same as `from_slice`, but allows custom patterns elements to be specified.
-    pub fn with_custom_patterns(seq: &[i32], pats: Vec<CustomPatternElem>) -> Self {
+    pub fn with_custom_patterns(seq: &[T], pats: Vec<CustomPatternElem<T>>) -> Self {
         Self::with_options(seq, false, pats)
     }
 
     /// Creates a new Analyzer, specifying custom pattern elements and whether meta-patterns
-    /// should be found.
-    pub fn with_options(seq: &[i32], meta: bool, pats: Vec<CustomPatternElem>) -> Self {
+    /// should be found. See `AnalyzerBuilder` for more options.
+    ///
+    /// A sequence of fewer than two terms has no transitions to analyze, so no patterns are found
+    /// for it; use `AnalyzerBuilder::build` to treat that as an error instead.
+    pub fn with_options(seq: &[T], meta: bool, pats: Vec<CustomPatternElem<T>>) -> Self {
+        Self::analyze(seq, &AnalyzerBuilder::new().meta_depth(if meta { 1 } else { 0 }).custom_patterns(pats))
+    }
+
+    pub(crate) fn with_builder(seq: &[T], options: &AnalyzerBuilder<T>) -> Result<Self, AnalyzerError> {
+        if seq.len() < 2 {
+            return Err(AnalyzerError::TooShort { len: seq.len() });
+        }
+
+        if options.overflow == OverflowPolicy::Reject {
+            if let Some(index) = seq.windows(2).position(|pair| pair[1].checked_sub(&pair[0]).is_none()) {
+                return Err(AnalyzerError::Overflow { index });
+            }
+        }
+
+        Ok(Self::analyze(seq, options))
+    }
+
+    pub(crate) fn analyze(seq: &[T], options: &AnalyzerBuilder<T>) -> Self {
+        let mod_bound = options.mod_bound(seq.iter().filter_map(Number::checked_abs).max().unwrap_or_else(T::zero));
+
         Analyzer {
-            meta: meta,
-            choices: (0..seq.len() - 1).map(|i|
-                         PatternElemChoice::from_i32_pair(seq[i], seq[i + 1], pats.clone())
+            seq: seq.to_vec(),
+            options: options.clone(),
+            choices: (1..seq.len()).map(|i|
+                         PatternElemChoice::from_window(&seq[..i], &seq[i], &mod_bound, options)
                      ).collect()
         }
     }
 
     /// Attempts to find exactly one pattern of `n` operations that described the given sequence.
-    pub fn find_any_pattern_of_length(&self, n: usize) -> Option<Pattern> {
-        // TODO: Short-circuit finding one pattern instead of all of them
-        self.find_patterns_of_length(n).pop()
+    pub fn find_any_pattern_of_length(&self, n: usize) -> Option<Pattern<T>> {
+        self.patterns_of_length(n).next_back()
     }
 
     /// Attempts to find exactly one pattern of maximum size `max` (in terms of number of
     /// operations) that describes the given sequence. It returns the smallest such pattern it can
     /// find .
-    pub fn find_any_pattern(&self, max: usize) -> Option<Pattern> {
-        for i in 1..max + 1 {
-            let mut vec = self.find_patterns_of_length(i);
+    pub fn find_any_pattern(&self, max: usize) -> Option<Pattern<T>> {
+        (1..max + 1).filter_map(|i| self.find_any_pattern_of_length(i)).next()
+    }
 
-            // TODO: Short-circuit finding one pattern instead of all of them
-            if !vec.is_empty() {
-                return vec.pop();
-            }
-        }
+    /// Finds the simplest pattern of `n` operations that describes the given sequence (see
+    /// `Pattern::cost`).
+    pub fn find_best_pattern_of_length(&self, n: usize) -> Option<Pattern<T>> {
+        self.pattern_set(n).best()
+    }
 
-        None
+    /// Finds the simplest pattern of maximum size `max` (in terms of number of operations) that
+    /// describes the given sequence. Unlike `find_any_pattern`, this doesn't stop at the smallest
+    /// size: a longer pattern is returned if it's simpler, which prefers e.g. `+2, -2` over a
+    /// meta-pattern of one operation. Ties are broken in favor of the shorter pattern.
+    ///
+    /// ```
+    /// # #[macro_use] extern crate sea_canal;
+    /// # use sea_canal::{Analyzer, Pattern};
+    /// # use sea_canal::PatternElem::{Plus, Square, SquareRoot};
+    /// # fn main() {
+    /// let analyzer = Analyzer::from_slice(&[2, 4, 2, 4]);
+    ///
+    /// assert_eq!(Some(pat![Square, SquareRoot]), analyzer.find_any_pattern(2));
+    /// assert_eq!(Some(pat![Plus(2), Plus(-2)]), analyzer.find_best_pattern(2));
+    /// # }
+    /// ```
+    pub fn find_best_pattern(&self, max: usize) -> Option<Pattern<T>> {
+        find_best_pattern(max, |i| self.find_best_pattern_of_length(i))
+    }
+
+    /// Same as `find_patterns`, but ordered from simplest to most complex (see `Pattern::cost`).
+    pub fn find_ranked_patterns(&self, max: usize) -> Vec<Pattern<T>> {
+        self.find_pattern_set(max).map(|set| set.ranked()).unwrap_or_default()
     }
 
     /// Finds all patterns with `n` operations that describe the given sequence.
-    pub fn find_patterns_of_length(&self, range: usize) -> Vec<Pattern> {
-        let mut pats = vec![Pattern::empty()];
+    pub fn find_patterns_of_length(&self, range: usize) -> Vec<Pattern<T>> {
+        self.patterns_of_length(range).collect()
+    }
 
-        for i in 0..range {
-            let choices: Vec<_> = step!(i => self.len(); range).map(|i| self.choices[i].clone()).collect();
+    /// Lazily iterates over the patterns with `n` operations that describe the given sequence, in
+    /// the same order as `find_patterns_of_length`. The operations that are valid at each position
+    /// of the pattern are found up front, but patterns are only constructed as they're needed.
+    ///
+    /// ```
+    /// # #[macro_use] extern crate sea_canal;
+    /// # use sea_canal::{Analyzer, Pattern};
+    /// # use sea_canal::PatternElem::{Const, Square, SquareRoot};
+    /// # fn main() {
+    /// let analyzer = Analyzer::from_slice(&[2, 4, 2, 4, 2]);
+    /// let mut pats = analyzer.patterns_of_length(2);
+    ///
+    /// assert_eq!(Some(pat![Const(4), Const(2)]), pats.next());
+    /// assert_eq!(Some(pat![Square, SquareRoot]), pats.next_back());
+    /// # }
+    /// ```
+    pub fn patterns_of_length(&self, n: usize) -> PatternIter<T> {
+        self.pattern_set(n).into_iter()
+    }
 
-            let meta_patterns = if self.meta {
-                self.find_meta_patterns(i, range)
-            } else {
-                Vec::new()
-            };
+    /// Finds the operations that are valid at each position of a pattern of `n` operations
+    /// describing the given sequence. Every combination of them is such a pattern.
+    ///
+    /// If no operation fits at some position, the set is empty, and the positions after it are
+    /// left empty without being analyzed.
+    ///
+    /// ```
+    /// # extern crate sea_canal;
+    /// # use sea_canal::Analyzer;
+    /// # use sea_canal::PatternElem::{Const, Div, Mult, Plus, Square, SquareRoot};
+    /// # fn main() {
+    /// let set = Analyzer::from_slice(&[2, 4, 2, 4, 2]).pattern_set(2);
+    ///
+    /// assert_eq!(&[Const(4), Mult(2), Plus(2), Square], set.position(0));
+    /// assert_eq!(&[Const(2), Div(2), Plus(-2), SquareRoot], set.position(1));
+    /// assert_eq!(16, set.count());
+    /// # }
+    /// ```
+    pub fn pattern_set(&self, n: usize) -> PatternSet<T> {
+        let mut positions = Vec::with_capacity(n);
 
-            let mut new = Vec::new();
+        for i in 0..n {
+            let last = step!(i => self.len(); n).last().unwrap_or(0);
+            let mut position: Vec<_> = intersection(step!(i => self.len(); n).map(|i| (i, &self.choices[i]))).into_iter().collect();
+            position.retain(|elem| is_tested(elem, last, self.len() + 1));
 
-            for pat in &mut pats {
-                let mut new_pats = Self::intersection(&choices[..]);
-                new_pats.extend(meta_patterns.clone());
-                new.extend(pat.extend_each(new_pats.into_iter()).into_iter());
+            if self.options.meta_depth > 0 {
+                position.extend(self.find_meta_patterns(i, n));
             }
 
-            pats = new;
+            let is_empty = position.is_empty();
+            positions.push(position);
 
-            // Makes results deterministic, which is helpful.
-            pats.sort();
+            // There's no need to look at the rest of the pattern if no operation fits here.
+            if is_empty {
+                positions.resize(n, Vec::new());
+                break;
+            }
         }
 
-        pats
+        PatternSet::new(positions)
+    }
+
+    /// Finds the set of patterns of minimal size (up to `max` operations) that describe the given
+    /// sequence, like `find_patterns`.
+    pub fn find_pattern_set(&self, max: usize) -> Option<PatternSet<T>> {
+        (1..max + 1).map(|i| self.pattern_set(i)).find(|set| !set.is_empty())
     }
 
     /// Finds patterns of maximum size `max` (in terms of number of operations) that describe the
     /// given sequence. It will return all such patterns that are of minimal size (i.e. if a
     /// sequence can be described by a pattern of two operations, it will return all such patterns,
     /// but none of size three or greater).
-    pub fn find_patterns(&self, max: usize) -> Vec<Pattern> {
+    pub fn find_patterns(&self, max: usize) -> Vec<Pattern<T>> {
         for i in 1..max + 1 {
             let vec = self.find_patterns_of_length(i);
 
@@ -106,24 +207,138 @@ impl Analyzer {
         Vec::new()
     }
 
+    /// Finds the pattern of `n` operations that describes the most transitions of the given
+    /// sequence, as long as it fails to describe no more of them than `tolerance` allows. Within
+    /// each position, the operation describing the most transitions is chosen, with ties going to
+    /// the simplest one (see `PatternElem::cost`). Meta-patterns aren't considered.
+    pub fn find_fuzzy_pattern_of_length(&self, n: usize, tolerance: Tolerance) -> Option<FuzzyMatch<T>> {
+        let mut elems = Vec::with_capacity(n);
+        let mut outliers = Vec::new();
+
+        for i in 0..n {
+            let indices: Vec<_> = step!(i => self.len(); n).collect();
+            let last = indices.last().cloned().unwrap_or(0);
+            let mut support = HashMap::new();
+
+            for &j in &indices {
+                for elem in self.choices[j].0.iter().filter(|elem| is_tested(elem, last, self.len() + 1)) {
+                    *support.entry(elem).or_insert(0) += 1;
+                }
+            }
+
+            let elem = support.into_iter()
+                              .min_by(|&(x, x_count), &(y, y_count)| {
+                                  y_count.cmp(&x_count).then_with(|| x.cost().cmp(&y.cost())).then_with(|| x.cmp(y))
+                              })
+                              .map(|(elem, _)| elem.clone())?;
+
+            outliers.extend(indices.into_iter().filter(|&j| !self.choices[j].admits(&elem, j)));
+            elems.push(elem);
+        }
+
+        if outliers.len() > tolerance.max_outliers(self.len()) {
+            return None;
+        }
+
+        outliers.sort();
+        Some(FuzzyMatch { pattern: Pattern::new(elems), outliers })
+    }
+
+    /// Finds a pattern of maximum size `max` (in terms of number of operations) that describes the
+    /// given sequence apart from the outliers allowed by `tolerance`, like
+    /// `find_fuzzy_pattern_of_length`. It returns the smallest such pattern it can find.
+    ///
+    /// ```
+    /// # #[macro_use] extern crate sea_canal;
+    /// # use sea_canal::{Analyzer, Pattern, Tolerance};
+    /// # use sea_canal::PatternElem::Plus;
+    /// # fn main() {
+    /// let analyzer = Analyzer::from_slice(&[1, 4, 3, 6, 99, 8, 7, 10, 9]);
+    /// let fuzzy = analyzer.find_fuzzy_pattern(2, Tolerance::AllBut(2)).unwrap();
+    ///
+    /// assert_eq!(pat![Plus(3), Plus(-1)], fuzzy.pattern);
+    /// assert_eq!(vec![3, 4], fuzzy.outliers);
+    /// # }
+    /// ```
+    pub fn find_fuzzy_pattern(&self, max: usize, tolerance: Tolerance) -> Option<FuzzyMatch<T>> {
+        (1..max + 1).filter_map(|i| self.find_fuzzy_pattern_of_length(i, tolerance)).next()
+    }
+
+    /// Finds the linear recurrence of the smallest order (up to `max_order`) that describes the
+    /// given sequence, which can describe sequences that no pattern can (see `LinearRecurrence`).
+    /// Wrapping it in `PatternElem::Linear` allows it to be used like any other pattern.
+    ///
+    /// ```
+    /// # #[macro_use] extern crate sea_canal;
+    /// # use sea_canal::{Analyzer, Pattern};
+    /// # use sea_canal::PatternElem::Linear;
+    /// # fn main() {
+    /// let pell = &[0, 1, 2, 5, 12, 29, 70];
+    /// let recurrence = Analyzer::from_slice(pell).find_linear_recurrence(3).unwrap();
+    ///
+    /// assert_eq!(vec![2, 1], recurrence.coefficients);
+    /// assert_eq!("linear [2, 1]", format!("{}", recurrence));
+    /// assert_eq!(vec![169, 408], pat![Linear(recurrence)].predict_next(pell, 2));
+    /// # }
+    /// ```
+    pub fn find_linear_recurrence(&self, max_order: usize) -> Option<LinearRecurrence<T>> {
+        LinearRecurrence::find(&self.seq, max_order)
+    }
+
+    /// Finds the simplest formula that gives each term of the given sequence from its index
+    /// (counting from 0), such as a polynomial of degree up to `max_degree` or a scaled factorial,
+    /// Fibonacci number, prime, power or triangular number (see `IndexFormula::find`). Wrapping it
+    /// in `PatternElem::Index` allows it to be used like any other pattern.
+    ///
+    /// ```
+    /// # #[macro_use] extern crate sea_canal;
+    /// # use sea_canal::{Analyzer, Pattern};
+    /// # use sea_canal::PatternElem::Index;
+    /// # fn main() {
+    /// let seq = &[1, 3, 9, 19, 33];
+    /// let formula = Analyzer::from_slice(seq).find_index_formula(3).unwrap();
+    ///
+    /// assert_eq!("a(n) = 2*n^2 +1", format!("{}", formula));
+    /// assert_eq!(vec![51, 73], pat![Index(formula)].predict_next(seq, 2));
+    /// # }
+    /// ```
+    pub fn find_index_formula(&self, max_degree: usize) -> Option<IndexFormula<T>> {
+        IndexFormula::find(&self.seq, max_degree)
+    }
+
     #[inline]
     fn len(&self) -> usize {
         self.choices.len()
     }
 
-    fn intersection(slice: &[PatternElemChoice]) -> HashSet<PatternElem> {
-        let base = match slice.first() {
-            Some(&PatternElemChoice(ref choices)) => choices.clone(),
-            None => return HashSet::new()
-        };
+    fn find_meta_patterns(&self, offset: usize, range: usize) -> Vec<PatternElem<T>> {
+        let choices: Vec<_> = step!(offset => self.len(); range).map(|i| self.choices[i].clone()).collect();
+        let meta_analyzer = MetaAnalyzer::new(choices, &self.options);
 
-        slice.into_iter().fold(base, |set, choice| set.intersection(&choice.0).cloned().collect())
+        meta_analyzer.find_patterns().into_iter().map(PatternElem::Meta).collect()
     }
+}
 
-    fn find_meta_patterns(&self, offset: usize, range: usize) -> Vec<PatternElem> {
-        let choices: Vec<_> = step!(offset => self.len(); range).map(|i| self.choices[i].clone()).collect();
-        let meta_analyzer = MetaAnalyzer::new(choices);
+/// Finds the simplest of the patterns returned by `best_of_length` for each length up to `max`,
+/// with ties going to the shorter pattern.
+pub(crate) fn find_best_pattern<T, F>(max: usize, mut best_of_length: F) -> Option<Pattern<T>>
+    where T: Number, F: FnMut(usize) -> Option<Pattern<T>> {
+    let mut best: Option<(usize, Pattern<T>)> = None;
 
-        meta_analyzer.find_patterns().into_iter().map(PatternElem::Meta).collect()
+    for i in 1..max + 1 {
+        // Every operation costs at least two, so no longer pattern can be any simpler.
+        if best.as_ref().is_some_and(|&(cost, _)| cost <= 2 * i) {
+            break;
+        }
+
+        if let Some(pat) = best_of_length(i) {
+            let cost = pat.cost();
+
+            if best.as_ref().is_none_or(|&(best_cost, _)| cost < best_cost) {
+                best = Some((cost, pat));
+            }
+        }
     }
+
+    best.map(|(_, pat)| pat)
 }
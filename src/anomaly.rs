@@ -0,0 +1,34 @@
+use std::fmt::{Display, Formatter, Error};
+
+use number::Number;
+use pattern::PatternElem;
+
+/// A term of a sequence that doesn't follow from the previous one the way a pattern expects (see
+/// `Pattern::anomalies`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Anomaly<T = i32> {
+    /// The index of the term in the sequence.
+    pub index: usize,
+    /// The operation that the pattern expects to lead to the term, or `None` if it couldn't be
+    /// determined (i.e. for an empty pattern, or a meta-pattern whose operands can't be
+    /// extrapolated that far).
+    pub elem: Option<PatternElem<T>>,
+    /// The term that the operation leads to, or `None` if it can't be computed (e.g. for custom
+    /// elements without an `apply` function).
+    pub expected: Option<T>,
+    pub actual: T,
+}
+
+impl<T: Number> Display for Anomaly<T> {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        write!(fmt, "term {}: ", self.index)?;
+
+        match (&self.expected, &self.elem) {
+            (Some(expected), Some(elem)) => write!(fmt, "expected {} ({})", expected, elem)?,
+            (None, Some(elem)) => write!(fmt, "expected {}", elem)?,
+            _ => write!(fmt, "no operation expected")?,
+        }
+
+        write!(fmt, ", found {}", self.actual)
+    }
+}
@@ -0,0 +1,205 @@
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter, Error};
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+use error::ParseNumberError;
+use number::Number;
+
+/// A finite floating-point number that is rounded to `DIGITS` significant (decimal) digits, which
+/// allows sequences of measurements to be analyzed despite rounding errors.
+///
+/// Every value (including the result of every operation) is rounded, so two numbers are equal when
+/// they agree to `DIGITS` significant digits. For instance, `0.1 + 0.2` is equal to `0.3`.
+///
+/// This isn't quite a tolerance: analyzing a sequence hashes and orders its numbers, which a
+/// comparison within a tolerance couldn't do consistently (it isn't transitive). As a result,
+/// numbers on either side of a rounding boundary are unequal however close they are (with 10
+/// digits, `0.12345678905001` and `0.12345678904999` differ by 2e-14, but round to
+/// `0.1234567891` and `0.123456789`), while numbers almost a whole unit of the last digit apart
+/// can be equal (`0.123456788951` and `0.12345678904999` both round to `0.123456789`). To make
+/// boundaries unlikely to matter, use a few more digits than the measurements are accurate to.
+#[derive(Clone, Copy, Debug)]
+pub struct Approx<const DIGITS: usize = 10>(f64);
+
+impl<const DIGITS: usize> Approx<DIGITS> {
+    /// Rounds `value` to `DIGITS` significant digits, returning `None` if it isn't finite.
+    pub fn new(value: f64) -> Option<Self> {
+        if !value.is_finite() {
+            return None;
+        }
+
+        // Going through the decimal representation rounds exactly, and rounding an already rounded
+        // value leaves it unchanged.
+        let rounded: f64 = format!("{:.*e}", DIGITS.saturating_sub(1), value).parse().ok()?;
+
+        // Normalizes negative zero so that it's equal to (and hashes the same as) zero.
+        Some(Approx(if rounded == 0.0 { 0.0 } else { rounded }))
+    }
+
+    pub fn value(&self) -> f64 {
+        self.0
+    }
+}
+
+impl<const DIGITS: usize> PartialEq for Approx<DIGITS> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+// Values are always finite, so equality is reflexive.
+impl<const DIGITS: usize> Eq for Approx<DIGITS> {}
+
+impl<const DIGITS: usize> Hash for Approx<DIGITS> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state)
+    }
+}
+
+impl<const DIGITS: usize> Ord for Approx<DIGITS> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl<const DIGITS: usize> PartialOrd for Approx<DIGITS> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const DIGITS: usize> Display for Approx<DIGITS> {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        write!(fmt, "{}", self.0)
+    }
+}
+
+/// Parses a number written the way it's displayed (or any other way an `f64` can be written),
+/// rounding it like `Approx::new`.
+impl<const DIGITS: usize> FromStr for Approx<DIGITS> {
+    type Err = ParseNumberError;
+
+    fn from_str(s: &str) -> Result<Self, ParseNumberError> {
+        s.parse().ok().and_then(Approx::new).ok_or(ParseNumberError)
+    }
+}
+
+impl<const DIGITS: usize> Number for Approx<DIGITS> {
+    fn zero() -> Self {
+        Approx(0.0)
+    }
+
+    fn one() -> Self {
+        Approx(1.0)
+    }
+
+    fn is_negative(&self) -> bool {
+        self.0 < 0.0
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        Approx::new(self.0 + other.0)
+    }
+
+    fn checked_sub(&self, other: &Self) -> Option<Self> {
+        Approx::new(self.0 - other.0)
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        Approx::new(self.0 * other.0)
+    }
+
+    fn exact_div(&self, other: &Self) -> Option<Self> {
+        Approx::new(self.0 / other.0)
+    }
+
+    fn checked_rem(&self, other: &Self) -> Option<Self> {
+        Approx::new(self.0 % other.0)
+    }
+
+    fn checked_abs(&self) -> Option<Self> {
+        Some(Approx(self.0.abs()))
+    }
+
+    fn checked_pow(&self, exp: u32) -> Option<Self> {
+        Approx::new(self.0.powi(i32::try_from(exp).ok()?))
+    }
+
+    fn exact_root(&self, n: u32) -> Option<Self> {
+        if n == 0 || (self.is_negative() && n.is_multiple_of(2)) {
+            return None;
+        }
+
+        let root = Approx::new(self.0.abs().powf(1.0 / f64::from(n)))?;
+        let root = if self.is_negative() { Approx(-root.0) } else { root };
+
+        if root.checked_pow(n).as_ref() == Some(self) {
+            Some(root)
+        } else {
+            None
+        }
+    }
+
+    fn from_u32(n: u32) -> Option<Self> {
+        Approx::new(f64::from(n))
+    }
+
+    fn to_u32(&self) -> Option<u32> {
+        if self.0.fract() == 0.0 && self.0 >= 0.0 && self.0 <= f64::from(u32::MAX) {
+            Some(self.0 as u32)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use error::ParseNumberError;
+    use number::Number;
+    use super::Approx;
+
+    fn approx(value: f64) -> Approx {
+        Approx::new(value).unwrap()
+    }
+
+    #[test]
+    fn new_rounds() {
+        assert_eq!(approx(0.3), approx(0.1 + 0.2));
+        assert_eq!(approx(0.0), approx(-0.0));
+        assert!(approx(0.3) != approx(0.3001));
+        assert_eq!(None, Approx::<10>::new(f64::NAN));
+        assert_eq!(None, Approx::<10>::new(f64::INFINITY));
+    }
+
+    #[test]
+    fn from_str() {
+        assert_eq!(Ok(approx(0.3)), "0.30000000000000004".parse());
+        assert_eq!(Ok(approx(-2.5)), approx(-2.5).to_string().parse());
+        assert_eq!(Err(ParseNumberError), "inf".parse::<Approx>());
+        assert_eq!(Err(ParseNumberError), "x".parse::<Approx>());
+    }
+
+    #[test]
+    fn rounding_boundaries() {
+        // Close numbers on either side of a boundary are unequal, and numbers almost a unit of the
+        // last digit apart can be equal.
+        assert!(approx(0.12345678905001) != approx(0.12345678904999));
+        assert_eq!(approx(0.123456788951), approx(0.12345678904999));
+        assert_eq!(Approx::<3>::new(1.25), Approx::<3>::new(1.2549));
+        assert!(Approx::<3>::new(1.2551) != Approx::<3>::new(1.2549));
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(Some(approx(0.3)), approx(0.1).checked_add(&approx(0.2)));
+        assert_eq!(Some(approx(0.5)), approx(1.0).exact_div(&approx(2.0)));
+        assert_eq!(None, approx(1.0).exact_div(&approx(0.0)));
+        assert_eq!(Some(approx(1.1)), approx(1.21).exact_root(2));
+        assert_eq!(Some(approx(-0.2)), approx(-0.008).exact_root(3));
+        assert_eq!(None, approx(-4.0).exact_root(2));
+        assert_eq!(None, approx(1.0).checked_pow(u32::MAX));
+    }
+}
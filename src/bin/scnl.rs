@@ -2,21 +2,27 @@ extern crate sea_canal;
 
 use std::env;
 use std::io;
+use std::process;
 
-use sea_canal::Analyzer;
+use sea_canal::{Analyzer, AnalyzerBuilder, Pattern};
 
 fn main() {
-    match env::args().nth(1) {
+    let mut args = env::args().skip(1);
+
+    match args.next() {
         Some(ref s) if s == "--sample"  => return sample(),
+        Some(ref s) if s == "--check" => return check(&args.collect::<Vec<_>>().join(" ")),
         _ => ()
     };
 
-    let stdin = io::stdin();
-    let mut buf = String::new();
-    stdin.read_line(&mut buf).expect("Unable to read input");
-    let split = buf.split_whitespace();
-    let nums : Vec<_> = split.map(|s| i32::from_str_radix(s, 10).expect("Invalid numeric input")).collect();
-    let analyzer = Analyzer::with_meta(&nums);
+    let nums = read_sequence();
+    let analyzer = match AnalyzerBuilder::new().meta_depth(1).build(&nums) {
+        Ok(analyzer) => analyzer,
+        Err(err) => {
+            eprintln!("Unable to analyze sequence: {}", err);
+            process::exit(1);
+        }
+    };
     let length = nums.len();
     let x = length - 1;
     let y = length / 2 + 1;
@@ -24,9 +30,39 @@ fn main() {
 
     println!("----------");
 
-    match analyzer.find_any_pattern(n) {
+    match analyzer.find_best_pattern(n) {
         Some(pat) => println!("{}", pat),
-        None => println!("No pattern found")
+        None => match analyzer.find_index_formula(n) {
+            Some(formula) => println!("{}", formula),
+            None => match analyzer.find_linear_recurrence(n) {
+                Some(recurrence) => println!("{}", recurrence),
+                None => println!("No pattern found")
+            }
+        }
+    };
+}
+
+fn read_sequence() -> Vec<i64> {
+    let stdin = io::stdin();
+    let mut buf = String::new();
+    stdin.read_line(&mut buf).expect("Unable to read input");
+    let split = buf.split_whitespace();
+    split.map(|s| s.parse::<i64>().expect("Invalid numeric input")).collect()
+}
+
+fn check(pat: &str) {
+    let pat = match pat.parse::<Pattern<i64>>() {
+        Ok(pat) => pat,
+        Err(err) => {
+            eprintln!("Unable to parse pattern: {}", err);
+            process::exit(1);
+        }
+    };
+    let nums = read_sequence();
+
+    match pat.first_anomaly(&nums) {
+        Some(anomaly) => println!("{}", anomaly),
+        None => println!("The pattern matches")
     };
 }
 
@@ -36,7 +72,7 @@ fn sample() {
     let analyzer = Analyzer::from_slice(s);
 
     println!("Patterns:");
-    for pat in analyzer.find_any_pattern(3) {
+    if let Some(pat) = analyzer.find_best_pattern(3) {
         println!("  {}", pat);
     }
 
@@ -44,7 +80,7 @@ fn sample() {
     println!("\nSequence: {:?}", s);
     let analyzer = Analyzer::from_slice(s);
 
-    for pat in analyzer.find_patterns_of_length(2) {
+    for pat in analyzer.find_ranked_patterns(2) {
         println!("  {}", pat);
     }
 
@@ -53,7 +89,7 @@ fn sample() {
     let analyzer = Analyzer::from_slice(s);
 
     println!("Patterns:");
-    for pat in analyzer.find_any_pattern_of_length(1) {
+    if let Some(pat) = analyzer.find_best_pattern_of_length(1) {
         println!("  {}", pat);
     }
 }
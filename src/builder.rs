@@ -0,0 +1,279 @@
+use std::collections::HashSet;
+
+use analyzer::Analyzer;
+use digit::DEFAULT_RADIX;
+use error::AnalyzerError;
+use number::Number;
+use pattern::CustomPatternElem;
+use stream::StreamingAnalyzer;
+
+/// The built-in operations that an analyzer can look for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Operation {
+    And,
+    Concat,
+    Const,
+    Cube,
+    CubeRoot,
+    DigitCount,
+    DigitProduct,
+    DigitSum,
+    Div,
+    Linear,
+    Mod,
+    Mult,
+    Or,
+    Plus,
+    PlusDigitSum,
+    Pow,
+    Product,
+    Reverse,
+    Root,
+    Rotate,
+    RotateLeft,
+    ShiftLeft,
+    ShiftRight,
+    Square,
+    SquareRoot,
+    Sum,
+    Xor,
+}
+
+impl Operation {
+    /// Every built-in operation.
+    pub fn all() -> Vec<Operation> {
+        vec![
+            Operation::And, Operation::Concat, Operation::Const, Operation::Cube, Operation::CubeRoot,
+            Operation::DigitCount, Operation::DigitProduct, Operation::DigitSum, Operation::Div, Operation::Linear,
+            Operation::Mod, Operation::Mult, Operation::Or, Operation::Plus, Operation::PlusDigitSum, Operation::Pow,
+            Operation::Product, Operation::Reverse, Operation::Root, Operation::Rotate, Operation::RotateLeft,
+            Operation::ShiftLeft, Operation::ShiftRight, Operation::Square, Operation::SquareRoot, Operation::Sum,
+            Operation::Xor,
+        ]
+    }
+
+    /// The operations on the digits of numbers (see `DigitOp`), which are only looked for once
+    /// they're enabled, since they depend on the radix and describe many transitions between
+    /// small numbers by coincidence.
+    pub fn digits() -> Vec<Operation> {
+        vec![
+            Operation::Concat, Operation::DigitCount, Operation::DigitProduct, Operation::DigitSum,
+            Operation::PlusDigitSum, Operation::Reverse, Operation::Rotate,
+        ]
+    }
+
+    /// The bitwise operations, which are only looked for once they're enabled, since every
+    /// transition between integers is an exclusive or.
+    pub fn bitwise() -> Vec<Operation> {
+        vec![
+            Operation::And, Operation::Or, Operation::RotateLeft, Operation::ShiftLeft, Operation::ShiftRight,
+            Operation::Xor,
+        ]
+    }
+}
+
+/// What an analyzer does when the operation between two terms of a sequence can't be represented
+/// by the number type (e.g. the difference between `i32::MIN` and `i32::MAX`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Leaves the operations that overflow out of the analysis, so patterns using them are never
+    /// found. This is the default.
+    Omit,
+    /// Fails to build the analyzer if the difference between two consecutive terms overflows,
+    /// which indicates that the sequence should be analyzed using a wider type.
+    Reject,
+}
+
+/// Configures which operations an `Analyzer` looks for and how hard it looks.
+///
+/// ```
+/// # #[macro_use] extern crate sea_canal;
+/// # use sea_canal::{AnalyzerBuilder, Operation, Pattern};
+/// # use sea_canal::PatternElem::Plus;
+/// # fn main() {
+/// let analyzer = AnalyzerBuilder::new().disable(Operation::Square)
+///                                      .disable(Operation::SquareRoot)
+///                                      .build(&[2, 4, 2, 4])
+///                                      .unwrap();
+///
+/// assert_eq!(Some(pat![Plus(2), Plus(-2)]), analyzer.find_any_pattern(2));
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct AnalyzerBuilder<T = i32> {
+    pub(crate) operations: HashSet<Operation>,
+    pub(crate) meta_depth: usize,
+    pub(crate) custom: Vec<CustomPatternElem<T>>,
+    pub(crate) overflow: OverflowPolicy,
+    pub(crate) max_modulus: Option<T>,
+    pub(crate) max_unchanged_moduli: usize,
+    pub(crate) max_trial_divisor: usize,
+    pub(crate) radix: u32,
+}
+
+/// The default maximum number of moduli that are considered for a transition that leaves a number
+/// unchanged.
+pub const MAX_UNCHANGED_MODULI: usize = 32;
+
+/// The default largest divisor tried when looking for the moduli of a transition, which keeps the
+/// search bounded for large numbers. Every modulus is found when the difference between the numbers
+/// is less than the square of this.
+pub const MAX_TRIAL_DIVISOR: usize = 1 << 16;
+
+impl<T: Number> AnalyzerBuilder<T> {
+    /// Creates a builder with every built-in operation but the digit and bitwise ones (see
+    /// `Operation::digits` and `Operation::bitwise`) and weighted sums (`Operation::Linear`)
+    /// enabled, no meta-patterns and no custom operations, which is equivalent to
+    /// `Analyzer::from_slice`.
+    pub fn new() -> Self {
+        let opt_in: Vec<_> = Operation::digits().into_iter()
+                                                .chain(Operation::bitwise())
+                                                .chain(Some(Operation::Linear))
+                                                .collect();
+
+        AnalyzerBuilder {
+            operations: Operation::all().into_iter().filter(|op| !opt_in.contains(op)).collect(),
+            meta_depth: 0,
+            custom: Vec::new(),
+            overflow: OverflowPolicy::Omit,
+            max_modulus: None,
+            max_unchanged_moduli: MAX_UNCHANGED_MODULI,
+            max_trial_divisor: MAX_TRIAL_DIVISOR,
+            radix: DEFAULT_RADIX,
+        }
+    }
+
+    pub fn enable(mut self, op: Operation) -> Self {
+        self.operations.insert(op);
+        self
+    }
+
+    pub fn disable(mut self, op: Operation) -> Self {
+        self.operations.remove(&op);
+        self
+    }
+
+    /// Enables exactly the given operations, disabling every other built-in one.
+    pub fn operations(mut self, ops: &[Operation]) -> Self {
+        self.operations = ops.iter().cloned().collect();
+        self
+    }
+
+    /// Sets how many levels of meta-patterns to look for; 0 disables them. At depth 2, the operands
+    /// of a meta-pattern can be described by a meta-pattern themselves (e.g. `[+1, +2, +4, +7...]`,
+    /// whose operands have differences of `+1, +2, +3`), and so on.
+    ///
+    /// Each level has one fewer operand than the level above it, so the search always ends, but
+    /// every level multiplies its cost.
+    pub fn meta_depth(mut self, depth: usize) -> Self {
+        self.meta_depth = depth;
+        self
+    }
+
+    pub fn custom_patterns(mut self, pats: Vec<CustomPatternElem<T>>) -> Self {
+        self.custom.extend(pats);
+        self
+    }
+
+    pub fn custom_pattern(mut self, pat: CustomPatternElem<T>) -> Self {
+        self.custom.push(pat);
+        self
+    }
+
+    pub fn overflow(mut self, policy: OverflowPolicy) -> Self {
+        self.overflow = policy;
+        self
+    }
+
+    /// Bounds the moduli that are considered. By default, moduli are only bounded (for transitions
+    /// that leave a number unchanged) by the largest magnitude in the sequence.
+    pub fn max_modulus(mut self, max: T) -> Self {
+        self.max_modulus = Some(max);
+        self
+    }
+
+    /// Sets the maximum number of moduli that are considered for a transition that leaves a number
+    /// unchanged (by default, `MAX_UNCHANGED_MODULI`).
+    pub fn max_unchanged_moduli(mut self, max: usize) -> Self {
+        self.max_unchanged_moduli = max;
+        self
+    }
+
+    /// Sets the largest divisor tried when looking for the moduli of a transition (by default,
+    /// `MAX_TRIAL_DIVISOR`).
+    pub fn max_trial_divisor(mut self, max: usize) -> Self {
+        self.max_trial_divisor = max;
+        self
+    }
+
+    /// Enables every digit operation, working in `radix`.
+    ///
+    /// ```
+    /// # #[macro_use] extern crate sea_canal;
+    /// # use sea_canal::{AnalyzerBuilder, DigitOp, Pattern};
+    /// # use sea_canal::PatternElem::{Digit, Plus};
+    /// # fn main() {
+    /// let analyzer = AnalyzerBuilder::new().digits(10).build(&[12, 21, 25, 52, 56, 65]).unwrap();
+    /// assert_eq!(Some(pat![Digit(DigitOp::Reverse, 10), Plus(4)]), analyzer.find_best_pattern(2));
+    /// # }
+    /// ```
+    pub fn digits(mut self, radix: u32) -> Self {
+        self.operations.extend(Operation::digits());
+        self.radix = radix;
+        self
+    }
+
+    /// Enables every bitwise operation.
+    ///
+    /// ```
+    /// # #[macro_use] extern crate sea_canal;
+    /// # use sea_canal::{AnalyzerBuilder, Pattern};
+    /// # use sea_canal::PatternElem::{Mult, Xor};
+    /// # fn main() {
+    /// let analyzer = AnalyzerBuilder::new().bitwise().build(&[3, 89, 178, 232, 464]).unwrap();
+    /// assert_eq!(Some(pat![Xor(90), Mult(2)]), analyzer.find_best_pattern(2));
+    /// # }
+    /// ```
+    pub fn bitwise(mut self) -> Self {
+        self.operations.extend(Operation::bitwise());
+        self
+    }
+
+    /// Sets the radix that digit operations work in (by default, `DEFAULT_RADIX`). Digit
+    /// operations don't apply to anything in a radix less than 2.
+    pub fn radix(mut self, radix: u32) -> Self {
+        self.radix = radix;
+        self
+    }
+
+    pub(crate) fn is_enabled(&self, op: Operation) -> bool {
+        self.operations.contains(&op)
+    }
+
+    /// Bounds the moduli for transitions that leave a number unchanged, given the largest
+    /// magnitude in the sequence.
+    pub(crate) fn mod_bound(&self, largest: T) -> T {
+        match self.max_modulus {
+            Some(ref max) if *max < largest => max.clone(),
+            _ => largest,
+        }
+    }
+
+    /// Analyzes a sequence with the configured options. Fails if the sequence has fewer than two
+    /// terms, or if an operation overflows and the overflow policy is `OverflowPolicy::Reject`.
+    pub fn build(&self, seq: &[T]) -> Result<Analyzer<T>, AnalyzerError> {
+        Analyzer::with_builder(seq, self)
+    }
+
+    /// Creates an analyzer that's given the terms of a sequence one at a time, keeping track of the
+    /// patterns of up to `max` operations.
+    pub fn build_streaming(&self, max: usize) -> StreamingAnalyzer<T> {
+        StreamingAnalyzer::with_builder(self.clone(), max)
+    }
+}
+
+impl<T: Number> Default for AnalyzerBuilder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,64 +1,492 @@
 use std::collections::HashSet;
 use std::iter::FromIterator;
 
-use pattern::{CustomPatternElem, PatternElem};
+use builder::{AnalyzerBuilder, Operation};
+use digit::{DigitOp, concatenation, rotations};
+use number::Number;
+use linear::LinearRecurrence;
+use pattern::PatternElem;
 
 /// A set of `PatternElems`, representing the set of valid operations at a given point in a sequence.
 #[derive(Clone, Debug)]
-pub struct PatternElemChoice(pub HashSet<PatternElem>);
+pub struct PatternElemChoice<T = i32>(pub HashSet<PatternElem<T>>);
 
-impl PatternElemChoice {
-    // TODO: Add ability to identify modulus.
-    pub fn from_i32_pair(x: i32, y: i32, pats: Vec<CustomPatternElem>) -> Self {
+impl<T: Number> PatternElemChoice<T> {
+    /// Finds the enabled operations that lead from `x` to `y`. Since any modulus greater than `x`
+    /// leaves it unchanged, moduli for such transitions are only included up to `mod_bound` (and at
+    /// most `max_unchanged_moduli` of them); analyzers use the largest magnitude in the sequence,
+    /// which is an upper bound on the moduli of every other transition.
+    pub fn from_pair(x: &T, y: &T, mod_bound: &T, options: &AnalyzerBuilder<T>) -> Self {
         let mut set = HashSet::new();
-        set.insert(PatternElem::Const(y));
-        set.insert(PatternElem::Plus(y - x));
 
-        if x != 0 && y % x == 0 {
-            set.insert(PatternElem::Mult(y / x));
+        if options.is_enabled(Operation::Const) {
+            set.insert(PatternElem::Const(y.clone()));
         }
 
-        if y != 0 && x % y == 0 {
-            set.insert(PatternElem::Div(x / y));
+        // Every operation is checked so that transitions that would overflow are simply omitted
+        // rather than panicking or wrapping into bogus candidates.
+        if options.is_enabled(Operation::Plus) {
+            if let Some(diff) = y.checked_sub(x) {
+                set.insert(PatternElem::Plus(diff));
+            }
         }
 
-        if x * x == y {
+        if options.is_enabled(Operation::Mult) {
+            if let Some(quot) = y.exact_div(x) {
+                set.insert(PatternElem::Mult(quot));
+            }
+        }
+
+        // Zero is only a quotient of zero, which can't be divided by it.
+        if options.is_enabled(Operation::Div) {
+            if let Some(quot) = x.exact_div(y).filter(|quot| *quot != T::zero()) {
+                set.insert(PatternElem::Div(quot));
+            }
+        }
+
+        if options.is_enabled(Operation::Square) && x.checked_pow(2).as_ref() == Some(y) {
             set.insert(PatternElem::Square);
         }
 
-        if y * y == x {
+        if options.is_enabled(Operation::SquareRoot) && y.checked_pow(2).as_ref() == Some(x) {
             set.insert(PatternElem::SquareRoot);
         }
 
-        if x * x * x == y {
+        if options.is_enabled(Operation::Cube) && x.checked_pow(3).as_ref() == Some(y) {
             set.insert(PatternElem::Cube);
         }
 
-        if y * y * y == x {
+        if options.is_enabled(Operation::CubeRoot) && y.checked_pow(3).as_ref() == Some(x) {
             set.insert(PatternElem::CubeRoot);
         }
 
-        for custom in pats {
-            if custom.check(x, y) {
-                set.insert(PatternElem::Custom(custom));
+        if options.is_enabled(Operation::Pow) {
+            set.extend(exponent(x, y).and_then(PatternElem::pow));
+        }
+
+        if options.is_enabled(Operation::Root) {
+            set.extend(exponent(y, x).and_then(PatternElem::root));
+        }
+
+        if options.is_enabled(Operation::Mod) {
+            let mut moduli = moduli(x, y, mod_bound, options.max_unchanged_moduli, options.max_trial_divisor);
+
+            if let Some(ref max) = options.max_modulus {
+                moduli.retain(|m| m <= max);
+            }
+
+            set.extend(moduli.into_iter().map(PatternElem::Mod));
+        }
+
+        let radix = options.radix;
+        let digit_ops = [
+            (Operation::DigitCount, DigitOp::Count), (Operation::DigitProduct, DigitOp::Product),
+            (Operation::DigitSum, DigitOp::Sum), (Operation::PlusDigitSum, DigitOp::PlusSum),
+            (Operation::Reverse, DigitOp::Reverse),
+        ];
+
+        for (op, digit_op) in digit_ops {
+            if options.is_enabled(op) && digit_op.apply(x, radix).as_ref() == Some(y) {
+                set.insert(PatternElem::Digit(digit_op, radix));
+            }
+        }
+
+        if options.is_enabled(Operation::Rotate) {
+            set.extend(rotations(x, y, radix).into_iter().map(|k| PatternElem::Digit(DigitOp::Rotate(k), radix)));
+        }
+
+        if options.is_enabled(Operation::Concat) {
+            set.extend(concatenation(x, y, radix).map(|c| PatternElem::Digit(DigitOp::Concat(c), radix)));
+        }
+
+        if options.is_enabled(Operation::Xor) {
+            set.extend(x.checked_xor(y).map(PatternElem::Xor));
+        }
+
+        // The mask of an `and` only clears the bits that were actually cleared, so that transitions
+        // clearing the same bits have the same mask; likewise, an `or` only sets the bits that were
+        // actually set.
+        if options.is_enabled(Operation::And) && y.checked_and(x).as_ref() == Some(y) {
+            let cleared = y.checked_not().and_then(|not_y| x.checked_and(&not_y));
+            set.extend(cleared.and_then(|bits| bits.checked_not()).map(PatternElem::And));
+        }
+
+        if options.is_enabled(Operation::Or) && x.checked_or(y).as_ref() == Some(y) {
+            set.extend(x.checked_xor(y).map(PatternElem::Or));
+        }
+
+        if options.is_enabled(Operation::ShiftLeft) {
+            set.extend(shift_places(x, y, true, T::exact_shl).into_iter().map(PatternElem::ShiftLeft));
+        }
+
+        if options.is_enabled(Operation::ShiftRight) {
+            set.extend(shift_places(x, y, false, T::exact_shr).into_iter().map(PatternElem::ShiftRight));
+        }
+
+        if options.is_enabled(Operation::RotateLeft) {
+            set.extend(bit_rotations(x, y).into_iter().map(PatternElem::RotateLeft));
+        }
+
+        for custom in &options.custom {
+            if custom.check(x.clone(), y.clone()) {
+                set.insert(PatternElem::Custom(custom.clone()));
             }
         }
 
         PatternElemChoice(set)
     }
+
+    /// Same as `from_pair`, but given every term before `y` (which mustn't be empty), so that
+    /// recurrences of up to `MAX_ORDER` terms are found too. Recurrences that need more terms than
+    /// there are before `y` are included regardless, since `y` may be one of the terms they start
+    /// from (see `is_tested`), except for weighted sums, whose weights aren't known yet.
+    pub fn from_window(prev: &[T], y: &T, mod_bound: &T, options: &AnalyzerBuilder<T>) -> Self {
+        let mut choice = Self::from_pair(&prev[prev.len() - 1], y, mod_bound, options);
+
+        for k in 2..MAX_ORDER + 1 {
+            let recurrences = [(Operation::Sum, PatternElem::Sum(k)), (Operation::Product, PatternElem::Product(k))];
+
+            for (op, elem) in recurrences {
+                if options.is_enabled(op) && elem.describes_after(prev, y) {
+                    choice.0.insert(elem);
+                }
+            }
+        }
+
+        if options.is_enabled(Operation::Linear) {
+            choice.0.extend(weighted_sums(prev, y).into_iter().map(PatternElem::Linear));
+        }
+
+        choice
+    }
+
+    /// Determines whether `elem` fits the transition this choice was found for, which leads to the
+    /// term at `index + 1`. Recurrences that need more terms than there are before it aren't
+    /// determined by it (see `weighted_sums`), so they fit regardless.
+    pub fn admits(&self, elem: &PatternElem<T>, index: usize) -> bool {
+        elem.order() > index + 1 || self.0.contains(elem)
+    }
 }
 
-impl IntoIterator for PatternElemChoice {
-    type Item = PatternElem;
-    type IntoIter = ::std::collections::hash_set::IntoIter<PatternElem>;
+/// Finds the operations that fit every one of `choices`, given along with their indices (see
+/// `PatternElemChoice::admits`). The recurrences that the first choice doesn't determine are taken
+/// from the second one.
+pub fn intersection<'a, T, I>(choices: I) -> HashSet<PatternElem<T>>
+    where T: Number + 'a, I: IntoIterator<Item = (usize, &'a PatternElemChoice<T>)> {
+    let mut choices = choices.into_iter();
+
+    let (first, mut set) = match choices.next() {
+        Some((index, choice)) => (index, choice.0.clone()),
+        None => return HashSet::new(),
+    };
+
+    for (n, (index, choice)) in choices.enumerate() {
+        if n == 0 {
+            set.extend(choice.0.iter().filter(|elem| elem.order() > first + 1).cloned());
+        }
+
+        set.retain(|elem| choice.admits(elem, index));
+    }
+
+    set
+}
+
+/// The largest magnitude of the weights in the recurrences found by `weighted_sums`.
+pub const MAX_WEIGHT: u32 = 3;
+
+/// Finds the recurrences `a(n) = c1 * a(n - 1) + c2 * a(n - 2)` with nonzero weights of magnitude
+/// up to `MAX_WEIGHT` (like the Pell numbers, with weights 2 and 1) that lead from `prev` to `y`.
+/// Without two terms before `y`, the weights can't be checked, so there are none. With both
+/// weights 1, the recurrence is `Sum(2)`, so it's left out.
+fn weighted_sums<T: Number>(prev: &[T], y: &T) -> Vec<LinearRecurrence<T>> {
+    let weights: Vec<T> = (1..MAX_WEIGHT + 1).filter_map(T::from_u32).flat_map(|w| {
+        let negated = T::zero().checked_sub(&w);
+        Some(w).into_iter().chain(negated)
+    }).collect();
+
+    let len = prev.len();
+    if len < 2 {
+        return Vec::new();
+    }
+
+    weights.iter()
+           .filter_map(|c2| {
+               // `y - c2 * a(n - 2)` has to be a small enough multiple of `a(n - 1)`.
+               let c1 = y.checked_sub(&c2.checked_mul(&prev[len - 2])?)?.exact_div(&prev[len - 1])?;
+               Some((c1, c2.clone())).filter(|pair| weights.contains(&pair.0))
+           })
+           .filter(|(c1, c2)| *c1 != T::one() || *c2 != T::one())
+           .map(|(c1, c2)| LinearRecurrence { coefficients: vec![c1, c2], constant: T::zero() })
+           .collect()
+}
+
+/// The largest number of previous terms that recurrences are found for.
+pub const MAX_ORDER: usize = 4;
+
+/// Determines whether an operation is actually tested by a position of a pattern whose last
+/// transition is `last`, in a sequence of `len` terms. Transitions without enough terms before
+/// them can't test a recurrence, so it has to be tested by a later one (and, so that e.g. any three
+/// terms don't count as a sum of two, by at least two transitions of the sequence).
+pub fn is_tested<T: Number>(elem: &PatternElem<T>, last: usize, len: usize) -> bool {
+    let order = elem.order();
+
+    // The weights of a weighted sum have to be determined too, so like `LinearRecurrence::find`,
+    // it takes twice as many terms.
+    let min_len = match *elem {
+        PatternElem::Linear(_) => 2 * order + 2,
+        _ => order + 2,
+    };
+
+    order <= 1 || (last + 1 >= order && len >= min_len)
+}
+
+/// Finds the positive moduli `m` for which `x % m == y`, where moduli that leave `x` unchanged are
+/// bounded by `bound` (and there are at most `max_unchanged` of them). Divisors are only tried up to
+/// `max_trial_divisor`, so every modulus is found when `x - y` is less than its square.
+fn moduli<T: Number>(x: &T, y: &T, bound: &T, max_unchanged: usize, max_trial_divisor: usize) -> Vec<T> {
+    let mut moduli = Vec::new();
+
+    if x == y {
+        let mut m = match x.checked_abs().and_then(|abs| abs.checked_add(&T::one())) {
+            Some(min) => min,
+            None => return moduli,
+        };
+
+        while m <= *bound && moduli.len() < max_unchanged {
+            moduli.push(m.clone());
+
+            m = match m.checked_add(&T::one()) {
+                Some(next) => next,
+                None => break,
+            };
+        }
+
+        return moduli;
+    }
+
+    // `x % m == y` means that `m` divides `x - y`, so the (integer) divisors of `x - y` are the
+    // only candidates.
+    let diff = match x.checked_sub(y).and_then(|diff| diff.checked_abs()) {
+        Some(ref diff) if divides(&T::one(), diff) => diff.clone(),
+        _ => return moduli,
+    };
+    let mut i = T::one();
+
+    for _ in 0..max_trial_divisor {
+        match i.checked_mul(&i) {
+            Some(ref sq) if *sq <= diff => (),
+            _ => break,
+        }
+
+        if divides(&i, &diff) {
+            if let Some(quot) = diff.exact_div(&i) {
+                moduli.push(i.clone());
+                moduli.push(quot);
+            }
+        }
+
+        i = match i.checked_add(&T::one()) {
+            Some(next) => next,
+            None => break,
+        };
+    }
+
+    moduli.sort();
+    moduli.dedup();
+    moduli.retain(|m| x.checked_rem(m).as_ref() == Some(y));
+    moduli
+}
+
+/// The largest exponent considered for `Pow` and `Root` operations.
+const MAX_EXPONENT: u32 = 64;
+
+/// Finds the exponent `n` (of at least 4, since smaller ones are covered by `Square` and `Cube`)
+/// for which `x^n == y`, up to `MAX_EXPONENT`. Powers of 0, 1 and -1 are ignored since any number
+/// of exponents would fit them.
+fn exponent<T: Number>(x: &T, y: &T) -> Option<T> {
+    let (base, target) = (x.checked_abs()?, y.checked_abs()?);
+
+    if base == T::zero() || base == T::one() {
+        return None;
+    }
+
+    // The magnitude of the powers only moves away from 1, so the search can stop once it passes
+    // that of `y`.
+    let growing = base > T::one();
+    let mut pow = x.checked_pow(3)?;
+
+    for n in 4..MAX_EXPONENT + 1 {
+        pow = pow.checked_mul(x)?;
+
+        if pow == *y {
+            return T::from_u32(n);
+        }
+
+        let magnitude = pow.checked_abs()?;
+
+        if (growing && magnitude > target) || (!growing && magnitude < target) {
+            return None;
+        }
+    }
+
+    None
+}
+
+/// Finds the number of places that `x` is shifted by to get `y`, where `grows` tells whether
+/// `shift` moves bits towards the high end, trying more places until the result passes `y` in
+/// magnitude or loses bits. 0 is ignored, since shifting it by any number of places gives 0.
+fn shift_places<T: Number, F: Fn(&T, u32) -> Option<T>>(x: &T, y: &T, grows: bool, shift: F) -> Option<T> {
+    if *x == T::zero() {
+        return None;
+    }
+
+    // Every place at least doubles or halves the magnitude, so once `y` is on the right side of
+    // `x`, it's passed within as many places as the larger of them has bits. Otherwise, a left
+    // shift of a type without a fixed width would never stop.
+    let target = y.checked_abs()?;
+
+    if (x.checked_abs()? < target) != grows {
+        return None;
+    }
+
+    let mut n = 1;
+
+    while let Some(shifted) = shift(x, n) {
+        if shifted == *y {
+            return T::from_u32(n);
+        }
+
+        let magnitude = shifted.checked_abs()?;
+
+        if (grows && magnitude > target) || (!grows && magnitude < target) {
+            return None;
+        }
+
+        n += 1;
+    }
+
+    None
+}
+
+/// Finds the numbers of places (less than the width of `T`) that the bits of `x` are rotated left
+/// by to get `y`. Numbers whose bits are all the same are ignored, since any rotation leaves them
+/// unchanged.
+fn bit_rotations<T: Number>(x: &T, y: &T) -> Vec<T> {
+    let width = match T::bit_width() {
+        Some(width) if x.rotate_bits_left(1).as_ref() != Some(x) => width,
+        _ => return Vec::new(),
+    };
+
+    (1..width).filter(|&n| x.rotate_bits_left(n).as_ref() == Some(y)).filter_map(T::from_u32).collect()
+}
+
+/// Determines whether `x` leaves no remainder when dividing `y`. Unlike `exact_div`, this is false
+/// for fractional types unless both numbers are integers.
+fn divides<T: Number>(x: &T, y: &T) -> bool {
+    y.checked_rem(x) == Some(T::zero())
+}
+
+impl<T> IntoIterator for PatternElemChoice<T> {
+    type Item = PatternElem<T>;
+    type IntoIter = ::std::collections::hash_set::IntoIter<PatternElem<T>>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.0.into_iter()
     }
 }
 
-impl FromIterator<PatternElem> for PatternElemChoice {
-    fn from_iter<I: IntoIterator<Item=PatternElem>>(iterator: I) -> Self {
+impl<T: Number> FromIterator<PatternElem<T>> for PatternElemChoice<T> {
+    fn from_iter<I: IntoIterator<Item=PatternElem<T>>>(iterator: I) -> Self {
         PatternElemChoice(iterator.into_iter().collect())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use builder::{AnalyzerBuilder, MAX_TRIAL_DIVISOR, MAX_UNCHANGED_MODULI, Operation};
+    use number::Number;
+    use super::PatternElemChoice;
+    use pattern::PatternElem;
+    use pattern::PatternElem::*;
+
+    fn choice_with(x: i32, y: i32, options: &AnalyzerBuilder) -> Vec<PatternElem> {
+        let mut elems: Vec<_> = PatternElemChoice::from_pair(&x, &y, &100, options).into_iter().collect();
+        elems.sort();
+        elems
+    }
+
+    fn choice_of(x: i32, y: i32) -> Vec<PatternElem> {
+        let mut elems: Vec<_> = PatternElemChoice::from_pair(&x, &y, &0, &AnalyzerBuilder::new()).into_iter().collect();
+        elems.sort();
+        elems
+    }
+
+    fn moduli<T: Number>(x: &T, y: &T, bound: &T) -> Vec<T> {
+        super::moduli(x, y, bound, MAX_UNCHANGED_MODULI, MAX_TRIAL_DIVISOR)
+    }
+
+    #[test]
+    fn choice_omits_overflowing_operations() {
+        assert_eq!(vec![Const(7), Mod(49993), Plus(-49993)], choice_of(50000, 7));
+        assert_eq!(vec![Const(-1), Mod(i32::MAX), Plus(i32::MAX)], choice_of(i32::MIN, -1));
+        assert_eq!(vec![Const(i32::MIN), Mult(i32::MIN)], choice_of(1, i32::MIN));
+        assert_eq!(vec![Const(i32::MIN + 1), Plus(1)], choice_of(i32::MIN, i32::MIN + 1));
+    }
+
+    #[test]
+    fn choice_from_zero() {
+        assert_eq!(vec![Const(12), Plus(12)], choice_of(0, 12));
+    }
+
+    #[test]
+    fn choice_omits_disabled_operations() {
+        let options = AnalyzerBuilder::new().operations(&[Operation::Plus, Operation::Mod]).max_modulus(6);
+
+        assert_eq!(vec![Mod(3), Mod(5), Plus(-15)], choice_with(17, 2, &options));
+        assert_eq!(vec![Const(4), Mult(2), Plus(2), Square], choice_with(2, 4, &AnalyzerBuilder::new().disable(Operation::Div)));
+        assert_eq!(vec![Const(2), Mod(3), Mod(4), Plus(0)], choice_with(2, 2, &AnalyzerBuilder::new().max_unchanged_moduli(2).operations(&[Operation::Const, Operation::Plus, Operation::Mod])));
+    }
+
+    #[test]
+    fn choice_of_powers() {
+        assert_eq!(vec![Const(81), Mult(27), Plus(78), Pow(4)], choice_of(3, 81));
+        assert_eq!(vec![Const(2), Div(512), Mod(7), Mod(14), Mod(73), Mod(146), Mod(511), Mod(1022), Plus(-1022), Root(10)], choice_of(1024, 2));
+        assert_eq!(vec![Const(-32), Mult(16), Plus(-30), Pow(5)], choice_of(-2, -32));
+        assert_eq!(vec![Const(1), Cube, CubeRoot, Div(1), Mult(1), Plus(0), Square, SquareRoot], choice_of(1, 1));
+    }
+
+    #[test]
+    fn moduli_of_distinct_pair() {
+        assert_eq!(vec![6, 12], moduli(&17, &5, &17));
+        assert_eq!(vec![3], moduli(&5, &2, &17));
+        assert_eq!(vec![2, 3, 6], moduli(&-7, &-1, &7));
+        assert_eq!(vec![7, 14], moduli(&16u64, &2, &16));
+        assert_eq!(Vec::<u64>::new(), moduli(&2u64, &16, &16));
+        assert_eq!(Vec::<i32>::new(), moduli(&2, &4, &17));
+        assert_eq!(Vec::<i32>::new(), moduli(&-7, &1, &17));
+    }
+
+    #[test]
+    fn weighted_sums_of_window() {
+        let sums = |prev: &[i32], y| -> Vec<_> {
+            let mut sums: Vec<_> = super::weighted_sums(prev, &y).into_iter().map(|linear| linear.coefficients).collect();
+            sums.sort();
+            sums
+        };
+
+        // 5 = 1 * 2 + 3 * 1 = 2 * 2 + 1 * 1 = 3 * 2 - 1 * 1
+        assert_eq!(vec![vec![1, 3], vec![2, 1], vec![3, -1]], sums(&[1, 2], 5));
+        assert_eq!(Vec::<Vec<i32>>::new(), sums(&[1, 2], 100));
+        // One previous term doesn't determine any weights.
+        assert_eq!(Vec::<Vec<i32>>::new(), sums(&[1], 5));
+        assert_eq!(Vec::<Vec<i32>>::new(), sums(&[], 5));
+    }
+
+    #[test]
+    fn moduli_of_identical_pair() {
+        assert_eq!(vec![3, 4, 5], moduli(&2, &2, &5));
+        assert_eq!(vec![1, 2], moduli(&0, &0, &2));
+        assert_eq!(Vec::<i32>::new(), moduli(&5, &5, &5));
+        assert_eq!((4..36).collect::<Vec<_>>(), moduli(&3, &3, &i32::MAX));
+    }
+}
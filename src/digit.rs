@@ -0,0 +1,193 @@
+use std::convert::TryFrom;
+use std::fmt::{Formatter, Error};
+
+use number::Number;
+
+/// The radix that digit operations work in unless another one is given.
+pub const DEFAULT_RADIX: u32 = 10;
+
+/// Operations on the digits of a non-negative integer, written in some radix (see
+/// `PatternElem::Digit`).
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DigitOp<T = i32> {
+    // Listed alphabetically, like `PatternElem`.
+    /// Appends the digits of a non-negative constant, e.g. 12 to 127 for `Concat(7)`.
+    Concat(T),
+    /// The number of digits, counting 0 as having one.
+    Count,
+    /// Adds the sum of the digits to the number, e.g. 12 to 15.
+    PlusSum,
+    /// The product of the digits.
+    Product,
+    /// Reverses the digits, dropping the zeros that end up leading, e.g. 120 to 21.
+    Reverse,
+    /// Moves the first `k` digits to the end, e.g. 1234 to 3412 for `Rotate(2)`.
+    Rotate(usize),
+    /// The sum of the digits.
+    Sum,
+}
+
+impl<T: Number> DigitOp<T> {
+    /// Applies the operation to `x` written in `radix`. Returns `None` if `x` is negative or not
+    /// an integer, if `radix` is less than 2, or if the result can't be represented by `T`.
+    pub fn apply(&self, x: &T, radix: u32) -> Option<T> {
+        let x_digits = digits(x, radix)?;
+
+        match *self {
+            DigitOp::Concat(ref c) => {
+                let shift = T::from_u32(radix)?.checked_pow(u32::try_from(digits(c, radix)?.len()).ok()?)?;
+                x.checked_mul(&shift)?.checked_add(c)
+            }
+            DigitOp::Count => T::from_u32(u32::try_from(x_digits.len()).ok()?),
+            DigitOp::PlusSum => x.checked_add(&sum(&x_digits)?),
+            DigitOp::Product => x_digits.iter().try_fold(T::one(), |product, d| product.checked_mul(d)),
+            DigitOp::Reverse => {
+                let reversed: Vec<_> = x_digits.into_iter().rev().collect();
+                from_digits(&reversed, radix)
+            }
+            DigitOp::Rotate(k) => {
+                let mut rotated = x_digits;
+                let len = rotated.len();
+                rotated.rotate_left(k % len);
+                from_digits(&rotated, radix)
+            }
+            DigitOp::Sum => sum(&x_digits),
+        }
+    }
+
+    /// Returns the operand of operations that take one.
+    pub fn get_operand(&self) -> Option<T> {
+        match *self {
+            DigitOp::Concat(ref c) => Some(c.clone()),
+            DigitOp::Rotate(k) => T::from_u32(u32::try_from(k).ok()?),
+            _ => None,
+        }
+    }
+
+    /// Returns an operation of the same type with a different operand, which must be non-negative
+    /// for concatenation and positive for rotations.
+    pub fn with_operand(&self, operand: T) -> Option<Self> {
+        match *self {
+            DigitOp::Concat(_) if !operand.is_negative() => Some(DigitOp::Concat(operand)),
+            DigitOp::Rotate(_) => match operand.to_u32()? {
+                0 => None,
+                k => Some(DigitOp::Rotate(usize::try_from(k).ok()?)),
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Finds the digits of `x` in `radix`, starting with the most significant one, if `x` is a
+/// non-negative integer.
+fn digits<T: Number>(x: &T, radix: u32) -> Option<Vec<T>> {
+    if radix < 2 || x.is_negative() {
+        return None;
+    }
+
+    let r = T::from_u32(radix)?;
+    let mut rest = x.clone();
+    let mut digits = Vec::new();
+
+    loop {
+        let digit = rest.checked_rem(&r)?;
+        rest = rest.checked_sub(&digit)?.exact_div(&r)?;
+        digits.push(digit);
+
+        if rest == T::zero() {
+            break;
+        }
+    }
+
+    digits.reverse();
+    Some(digits)
+}
+
+fn from_digits<T: Number>(digits: &[T], radix: u32) -> Option<T> {
+    let r = T::from_u32(radix)?;
+    digits.iter().try_fold(T::zero(), |n, d| n.checked_mul(&r)?.checked_add(d))
+}
+
+fn sum<T: Number>(digits: &[T]) -> Option<T> {
+    digits.iter().try_fold(T::zero(), |sum, d| sum.checked_add(d))
+}
+
+/// Finds the rotations `k` (less than the number of digits) that lead from `x` to `y`.
+pub(crate) fn rotations<T: Number>(x: &T, y: &T, radix: u32) -> Vec<usize> {
+    let len = digits(x, radix).map_or(0, |digits| digits.len());
+    (1..len).filter(|&k| DigitOp::Rotate(k).apply(x, radix).as_ref() == Some(y)).collect()
+}
+
+/// Finds the constant whose digits are appended to `x` to make `y`, if there is one. The constant
+/// can't have leading zeros, since they wouldn't be displayed.
+pub(crate) fn concatenation<T: Number>(x: &T, y: &T, radix: u32) -> Option<T> {
+    let (prefix, all) = (digits(x, radix)?, digits(y, radix)?);
+
+    if all.len() <= prefix.len() || !all.starts_with(&prefix) {
+        return None;
+    }
+
+    let suffix = &all[prefix.len()..];
+
+    if suffix[0] == T::zero() && suffix.len() > 1 {
+        return None;
+    }
+
+    from_digits(suffix, radix)
+}
+
+/// Displays the operation, e.g. `digit sum` or `concat 7`, followed by the radix unless it's
+/// `DEFAULT_RADIX` (e.g. `reverse (base 2)`).
+pub(crate) fn fmt_digit_op<T: Number>(fmt: &mut Formatter, op: &DigitOp<T>, radix: u32) -> Result<(), Error> {
+    match *op {
+        DigitOp::Concat(ref c) => write!(fmt, "concat {}", c)?,
+        DigitOp::Count => write!(fmt, "digit count")?,
+        DigitOp::PlusSum => write!(fmt, "+digit sum")?,
+        DigitOp::Product => write!(fmt, "digit product")?,
+        DigitOp::Reverse => write!(fmt, "reverse")?,
+        DigitOp::Rotate(k) => write!(fmt, "rotate {}", k)?,
+        DigitOp::Sum => write!(fmt, "digit sum")?,
+    }
+
+    if radix == DEFAULT_RADIX {
+        Ok(())
+    } else {
+        write!(fmt, " (base {})", radix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DigitOp, concatenation, rotations};
+
+    #[test]
+    fn apply() {
+        assert_eq!(Some(8), DigitOp::Sum.apply(&1250, 10));
+        assert_eq!(Some(3), DigitOp::Sum.apply(&0b1011, 2));
+        assert_eq!(Some(0), DigitOp::Product.apply(&1250, 10));
+        assert_eq!(Some(24), DigitOp::Product.apply(&234, 10));
+        assert_eq!(Some(521), DigitOp::Reverse.apply(&1250, 10));
+        assert_eq!(Some(0b1101), DigitOp::Reverse.apply(&0b1011, 2));
+        assert_eq!(Some(5012), DigitOp::Rotate(2).apply(&1250, 10));
+        assert_eq!(Some(4), DigitOp::Count.apply(&1250, 10));
+        assert_eq!(Some(1), DigitOp::Count.apply(&0, 10));
+        assert_eq!(Some(128), DigitOp::PlusSum.apply(&118, 10));
+        assert_eq!(Some(1234), DigitOp::Concat(34).apply(&12, 10));
+        assert_eq!(Some(0xab), DigitOp::Concat(0xb).apply(&0xa, 16));
+        assert_eq!(None, DigitOp::Sum.apply(&-12, 10));
+        assert_eq!(None, DigitOp::Sum.apply(&12, 1));
+        assert_eq!(None, DigitOp::Reverse.apply(&1_000_000_009, 10));
+    }
+
+    #[test]
+    fn operands_from_pairs() {
+        assert_eq!(vec![1, 3], rotations(&1212, &2121, 10));
+        assert_eq!(Vec::<usize>::new(), rotations(&1234, &4321, 10));
+        assert_eq!(Some(56), concatenation(&1234, &123456, 10));
+        assert_eq!(Some(0), concatenation(&12, &120, 10));
+        assert_eq!(None, concatenation(&12, &1205, 10));
+        assert_eq!(None, concatenation(&12, &12, 10));
+        assert_eq!(None, concatenation(&12, &312, 10));
+    }
+}
@@ -0,0 +1,85 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+/// The reasons a sequence can't be analyzed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AnalyzerError {
+    /// The sequence has fewer than two terms, so there are no transitions between them to analyze.
+    TooShort { len: usize },
+    /// The difference between the terms at `index` and `index + 1` can't be represented by the
+    /// number type (only reported with `OverflowPolicy::Reject`).
+    Overflow { index: usize },
+}
+
+impl Display for AnalyzerError {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            AnalyzerError::TooShort { len: 1 } => write!(fmt, "at least two terms are needed, but only one was given"),
+            AnalyzerError::TooShort { len } => write!(fmt, "at least two terms are needed, but {} were given", len),
+            AnalyzerError::Overflow { index } =>
+                write!(fmt, "the difference between terms {} and {} overflows", index, index + 1),
+        }
+    }
+}
+
+impl Error for AnalyzerError {}
+
+/// The ways that text can fail to describe a pattern.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// There's nothing between two commas (or in the whole text, when parsing an operation).
+    MissingOperation,
+    /// The operation isn't a built-in one, and no custom operation with its name is known.
+    UnknownOperation,
+    /// The operand can't be parsed as a number.
+    InvalidOperand,
+    /// The exponent of a power or root isn't an integer of at least 2.
+    InvalidExponent,
+    /// A `[` has no matching `]`, or a `]` has no matching `[`.
+    UnbalancedBracket,
+    /// The operations of a meta-pattern aren't followed by `...`.
+    MissingEllipsis,
+}
+
+/// An error from parsing a pattern, pointing at the part of the text that couldn't be parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    /// The offending part of the text.
+    pub token: String,
+    /// The byte offset of `token` in the text.
+    pub position: usize,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), fmt::Error> {
+        let description = match self.kind {
+            ParseErrorKind::MissingOperation => "missing operation",
+            ParseErrorKind::UnknownOperation => "unknown operation",
+            ParseErrorKind::InvalidOperand => "invalid operand",
+            ParseErrorKind::InvalidExponent => "invalid exponent",
+            ParseErrorKind::UnbalancedBracket => "unbalanced bracket",
+            ParseErrorKind::MissingEllipsis => "meta-pattern without an ellipsis",
+        };
+
+        if self.token.is_empty() {
+            write!(fmt, "{} at position {}", description, self.position)
+        } else {
+            write!(fmt, "{} `{}` at position {}", description, self.token, self.position)
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// An error from parsing a `Rational` or an `Approx` written the way it's displayed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParseNumberError;
+
+impl Display for ParseNumberError {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(fmt, "invalid number")
+    }
+}
+
+impl Error for ParseNumberError {}
@@ -0,0 +1,53 @@
+use pattern::Pattern;
+
+/// How many transitions of a sequence a pattern can fail to describe and still be accepted by
+/// `Analyzer::find_fuzzy_pattern`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Tolerance {
+    /// The pattern has to describe at least this fraction (between 0 and 1) of the transitions.
+    /// Fractions outside that range are clamped to it, and NaN requires every transition.
+    Fraction(f64),
+    /// The pattern can fail to describe at most this many transitions.
+    AllBut(usize),
+}
+
+impl Tolerance {
+    /// Returns the number of outliers allowed among `len` transitions.
+    pub fn max_outliers(&self, len: usize) -> usize {
+        match *self {
+            Tolerance::Fraction(fraction) => {
+                let fraction = if fraction.is_nan() { 1.0 } else { fraction.clamp(0.0, 1.0) };
+                let required = (fraction * len as f64).ceil() as usize;
+                len - required.min(len)
+            }
+            Tolerance::AllBut(k) => k.min(len),
+        }
+    }
+}
+
+/// A pattern that describes a sequence apart from a few outliers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FuzzyMatch<T = i32> {
+    pub pattern: Pattern<T>,
+    /// The transitions that the pattern doesn't describe, in ascending order, where transition `i`
+    /// is the one from the `i`th term of the sequence to the next. A single corrupted term usually
+    /// breaks both of the transitions next to it.
+    pub outliers: Vec<usize>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Tolerance;
+
+    #[test]
+    fn max_outliers() {
+        assert_eq!(2, Tolerance::Fraction(0.8).max_outliers(10));
+        assert_eq!(1, Tolerance::Fraction(0.75).max_outliers(7));
+        assert_eq!(0, Tolerance::Fraction(1.5).max_outliers(7));
+        assert_eq!(7, Tolerance::Fraction(0.0).max_outliers(7));
+        assert_eq!(7, Tolerance::Fraction(-0.5).max_outliers(7));
+        assert_eq!(0, Tolerance::Fraction(f64::NAN).max_outliers(7));
+        assert_eq!(3, Tolerance::AllBut(3).max_outliers(7));
+        assert_eq!(2, Tolerance::AllBut(3).max_outliers(2));
+    }
+}
@@ -0,0 +1,340 @@
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter, Error};
+
+use linear::solve;
+use number::Number;
+use pattern::PatternElem;
+
+/// The largest number of terms of a family that an index formula skips before the ones a sequence
+/// starts with, e.g. 1 for the Fibonacci numbers starting `1, 1, 2`. `IndexFormula::find` tries
+/// each start up to it, and formulas with larger ones are rejected when they're parsed or
+/// deserialized.
+pub const MAX_START: usize = 3;
+
+/// A well-known sequence, whose `n`th term (counting from 0) is computed directly from `n`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum IndexFamily<T = i32> {
+    /// `n!`, i.e. `1, 1, 2, 6, 24, ...`
+    Factorial,
+    /// The Fibonacci numbers, `0, 1, 1, 2, 3, ...`
+    Fibonacci,
+    /// The powers `b^n` of a base `b`.
+    Power(T),
+    /// The primes, `2, 3, 5, 7, 11, ...`
+    Prime,
+    /// The triangular numbers `n * (n + 1) / 2`, i.e. `0, 1, 3, 6, 10, ...`
+    Triangular,
+}
+
+/// Describes the `n`th term of a sequence (counting from 0) by its index alone, rather than by the
+/// terms before it, e.g. `a(n) = 2*n^2 +1` or `a(n) = prime(n)`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum IndexFormula<T = i32> {
+    /// A polynomial in `n`, given by its coefficients starting with the constant one (so
+    /// `vec![1, 0, 2]` is `2*n^2 +1`).
+    Polynomial(Vec<T>),
+    /// `a(n) = scale * f(n + start) + offset` for a family `f`.
+    Scaled { family: IndexFamily<T>, scale: T, start: usize, offset: T },
+}
+
+impl<T: Number> IndexFamily<T> {
+    /// Computes the `n`th term of the family, or `None` if `T` can't represent it. Every term
+    /// before it is generated along the way, so to compute several of them, use `terms`.
+    pub fn term(&self, n: usize) -> Option<T> {
+        self.terms().nth(n)
+    }
+
+    /// Generates the terms of the family in order, each from the ones before it, until one can't
+    /// be represented by `T`.
+    pub fn terms(&self) -> impl Iterator<Item = T> + '_ {
+        let (mut a, mut b) = (T::zero(), T::one());
+        let mut product = T::one();
+        let mut primes: Vec<u32> = Vec::new();
+
+        (0..u32::MAX).map_while(move |n| match *self {
+            IndexFamily::Factorial => {
+                // `product` holds the previous term, starting from the empty product.
+                if n > 0 {
+                    product = product.checked_mul(&T::from_u32(n)?)?;
+                }
+
+                Some(product.clone())
+            }
+            IndexFamily::Power(ref base) => {
+                if n > 0 {
+                    product = product.checked_mul(base)?;
+                }
+
+                Some(product.clone())
+            }
+            IndexFamily::Fibonacci => {
+                let next = a.checked_add(&b)?;
+                let term = a.clone();
+                a = b.clone();
+                b = next;
+                Some(term)
+            }
+            IndexFamily::Prime => {
+                // Only the primes found so far need to be tried as divisors.
+                let mut p = primes.last().map_or(Some(2), |&p| p.checked_add(1))?;
+
+                while primes.iter()
+                            .take_while(|&&d| d.checked_mul(d).is_some_and(|square| square <= p))
+                            .any(|&d| p.is_multiple_of(d)) {
+                    p = p.checked_add(1)?;
+                }
+
+                primes.push(p);
+                T::from_u32(p)
+            }
+            IndexFamily::Triangular => {
+                // `a` holds the previous term, the sum of the numbers up to `n - 1`.
+                a = a.checked_add(&T::from_u32(n)?)?;
+                Some(a.clone())
+            }
+        })
+    }
+}
+
+impl<T: Number> IndexFormula<T> {
+    /// Computes the `n`th term of the sequence (counting from 0), or `None` if `T` can't
+    /// represent it. Like `IndexFamily::term`, use `terms` to compute several of them.
+    pub fn term(&self, n: usize) -> Option<T> {
+        self.terms().nth(n)
+    }
+
+    /// Generates the terms of the sequence in order, until one can't be represented by `T`.
+    pub fn terms(&self) -> Box<dyn Iterator<Item = T> + '_> {
+        match *self {
+            IndexFormula::Polynomial(ref coefficients) => Box::new((0..u32::MAX).map_while(move |n| {
+                let x = T::from_u32(n)?;
+                coefficients.iter().rev().try_fold(T::zero(), |sum, c| sum.checked_mul(&x)?.checked_add(c))
+            })),
+            IndexFormula::Scaled { ref family, ref scale, start, ref offset } => {
+                Box::new(family.terms().skip(start).map_while(move |term| term.checked_mul(scale)?.checked_add(offset)))
+            }
+        }
+    }
+
+    /// Finds the simplest formula (see `PatternElem::cost`) that describes every term of `seq`
+    /// by its index: a polynomial of degree up to `max_degree`, or a scaled and offset family
+    /// skipping up to `MAX_START` of its terms. Each of the unknowns of a formula needs a term to
+    /// determine it, so to be sure that the formula isn't a coincidence, `seq` has to have at
+    /// least two more terms than it has unknowns.
+    pub fn find(seq: &[T], max_degree: usize) -> Option<Self> {
+        let polynomial = (0..max_degree + 1).take_while(|&d| seq.len() >= d + 3)
+                                            .filter_map(|d| Self::find_polynomial(seq, d))
+                                            .next();
+
+        let mut families = Vec::new();
+
+        for start in 0..MAX_START + 1 {
+            for family in [IndexFamily::Factorial, IndexFamily::Fibonacci, IndexFamily::Prime, IndexFamily::Triangular] {
+                families.push((family, start));
+            }
+        }
+
+        // The ratio between successive differences of `scale * b^n + offset` is `b`.
+        if let Some(base) = power_base(seq) {
+            families.push((IndexFamily::Power(base), 0));
+        }
+
+        let scaled = families.into_iter().filter_map(|(family, start)| Self::find_scaled(seq, family, start));
+
+        polynomial.into_iter().chain(scaled).min_by_key(|formula| PatternElem::Index(formula.clone()).cost())
+    }
+
+    fn find_polynomial(seq: &[T], degree: usize) -> Option<Self> {
+        let rows = (0..seq.len()).map(|n| {
+            let x = T::from_u32(u32::try_from(n).ok()?)?;
+            let mut row = vec![T::one()];
+
+            for _ in 0..degree {
+                let power = row.last()?.checked_mul(&x)?;
+                row.push(power);
+            }
+
+            row.push(seq[n].clone());
+            Some(row)
+        }).collect::<Option<_>>()?;
+
+        Self::verified(seq, IndexFormula::Polynomial(solve(rows, degree + 1)?))
+    }
+
+    /// Finds the scale and offset of `family` from the first four terms of `seq`, then checks the
+    /// rest as the family's terms are generated, so that it's ruled out at the first mismatch.
+    fn find_scaled(seq: &[T], family: IndexFamily<T>, start: usize) -> Option<Self> {
+        if seq.len() < 4 {
+            return None;
+        }
+
+        let (scale, offset) = {
+            let mut terms = family.terms().skip(start);
+            let first: Vec<_> = terms.by_ref().take(4).collect();
+
+            if first.len() < 4 {
+                return None;
+            }
+
+            let rows = first.iter().zip(seq).map(|(x, y)| vec![x.clone(), T::one(), y.clone()]).collect();
+            let solution = solve(rows, 2)?;
+
+            // A scale of zero describes a constant sequence, which is a polynomial instead.
+            if solution[0] == T::zero() {
+                return None;
+            }
+
+            let mut terms = first.into_iter().chain(terms);
+
+            for y in seq {
+                let term = terms.next()?.checked_mul(&solution[0])?.checked_add(&solution[1])?;
+
+                if term != *y {
+                    return None;
+                }
+            }
+
+            (solution[0].clone(), solution[1].clone())
+        };
+
+        Some(IndexFormula::Scaled { family, scale, start, offset })
+    }
+
+    fn verified(seq: &[T], formula: Self) -> Option<Self> {
+        if seq.iter().enumerate().all(|(n, y)| formula.term(n).as_ref() == Some(y)) {
+            Some(formula)
+        } else {
+            None
+        }
+    }
+}
+
+/// Guesses the base of a sequence of powers from its first three terms, if it's one other than
+/// -1, 0 or 1 (which don't tell the terms apart).
+fn power_base<T: Number>(seq: &[T]) -> Option<T> {
+    let first = seq.get(1)?.checked_sub(&seq[0])?;
+    let second = seq.get(2)?.checked_sub(&seq[1])?;
+    let base = second.exact_div(&first)?;
+
+    if base.checked_abs()? > T::one() {
+        Some(base)
+    } else {
+        None
+    }
+}
+
+/// Displays `c * x`, leaving out a coefficient of 1 (and writing one of -1 as a sign).
+fn write_scaled<T: Number>(fmt: &mut Formatter, c: &T, x: &str) -> Result<(), Error> {
+    if *c == T::one() {
+        write!(fmt, "{}", x)
+    } else if Some(c) == T::zero().checked_sub(&T::one()).as_ref() {
+        write!(fmt, "-{}", x)
+    } else {
+        write!(fmt, "{}*{}", c, x)
+    }
+}
+
+/// Displays the formula as `a(n) = ...`, e.g. `a(n) = n^2 -3*n +1`, `a(n) = 2*fib(n+1) -1` or
+/// `a(n) = 3*(-2)^n`. Terms after the first are preceded by their sign.
+impl<T: Number> Display for IndexFormula<T> {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        write!(fmt, "a(n) = ")?;
+
+        match *self {
+            IndexFormula::Polynomial(ref coefficients) => {
+                let mut terms = coefficients.iter().enumerate().rev().filter(|&(_, c)| *c != T::zero()).peekable();
+
+                if terms.peek().is_none() {
+                    return write!(fmt, "{}", T::zero());
+                }
+
+                for (i, (degree, c)) in terms.enumerate() {
+                    if i != 0 {
+                        write!(fmt, " {}", if c.is_negative() { "" } else { "+" })?;
+                    }
+
+                    match degree {
+                        0 => write!(fmt, "{}", c)?,
+                        1 => write_scaled(fmt, c, "n")?,
+                        _ => write_scaled(fmt, c, &format!("n^{}", degree))?,
+                    }
+                }
+
+                Ok(())
+            }
+            IndexFormula::Scaled { ref family, ref scale, start, ref offset } => {
+                let n = if start == 0 { String::from("n") } else { format!("n+{}", start) };
+                let term = match *family {
+                    IndexFamily::Factorial => format!("fact({})", n),
+                    IndexFamily::Fibonacci => format!("fib({})", n),
+                    IndexFamily::Prime => format!("prime({})", n),
+                    IndexFamily::Triangular => format!("tri({})", n),
+                    IndexFamily::Power(ref b) => {
+                        let b = if b.is_negative() { format!("({})", b) } else { b.to_string() };
+                        let n = if start == 0 { n } else { format!("({})", n) };
+                        format!("{}^{}", b, n)
+                    }
+                };
+
+                write_scaled(fmt, scale, &term)?;
+
+                if *offset == T::zero() {
+                    Ok(())
+                } else if offset.is_negative() {
+                    write!(fmt, " {}", offset)
+                } else {
+                    write!(fmt, " +{}", offset)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IndexFamily, IndexFormula};
+
+    fn scaled(family: IndexFamily, scale: i32, start: usize, offset: i32) -> IndexFormula {
+        IndexFormula::Scaled { family, scale, start, offset }
+    }
+
+    #[test]
+    fn terms() {
+        let first = |family: IndexFamily| (0..7).map(|n| family.term(n).unwrap()).collect::<Vec<_>>();
+
+        assert_eq!(vec![1, 1, 2, 6, 24, 120, 720], first(IndexFamily::Factorial));
+        assert_eq!(vec![0, 1, 1, 2, 3, 5, 8], first(IndexFamily::Fibonacci));
+        assert_eq!(vec![1, -3, 9, -27, 81, -243, 729], first(IndexFamily::Power(-3)));
+        assert_eq!(vec![2, 3, 5, 7, 11, 13, 17], first(IndexFamily::Prime));
+        assert_eq!(vec![0, 1, 3, 6, 10, 15, 21], first(IndexFamily::Triangular));
+        assert_eq!(None, IndexFamily::<i32>::Factorial.term(13));
+        assert_eq!(Some(5000050000i64), IndexFamily::Triangular.term(100000));
+        assert_eq!(Some(7), IndexFormula::Polynomial(vec![-1, 0, 2]).term(2));
+    }
+
+    #[test]
+    fn find() {
+        assert_eq!(Some(IndexFormula::Polynomial(vec![1, 0, 2])), IndexFormula::find(&[1, 3, 9, 19, 33], 3));
+        assert_eq!(Some(IndexFormula::Polynomial(vec![0, 0, 1])), IndexFormula::find(&[0, 1, 4, 9, 16], 3));
+        assert_eq!(Some(scaled(IndexFamily::Triangular, 1, 0, 0)), IndexFormula::find(&[0, 1, 3, 6, 10], 3));
+        assert_eq!(Some(scaled(IndexFamily::Factorial, 1, 1, 0)), IndexFormula::find(&[1, 2, 6, 24, 120], 3));
+        assert_eq!(Some(scaled(IndexFamily::Fibonacci, 2, 1, -1)), IndexFormula::find(&[1, 1, 3, 5, 9, 15], 3));
+        assert_eq!(Some(scaled(IndexFamily::Prime, 1, 2, 0)), IndexFormula::find(&[5, 7, 11, 13], 3));
+        assert_eq!(Some(scaled(IndexFamily::Power(2), 3, 0, 1)), IndexFormula::find(&[4, 7, 13, 25], 3));
+        assert_eq!(None, IndexFormula::find(&[1, 3, 9, 19], 3));
+        assert_eq!(None, IndexFormula::find(&[1, 2, 4, 7, 11, 17, 20, 30], 3));
+    }
+
+    #[test]
+    fn fmt() {
+        assert_eq!("a(n) = 2*n^2 +1", format!("{}", IndexFormula::Polynomial(vec![1, 0, 2])));
+        assert_eq!("a(n) = -n^3 -3*n", format!("{}", IndexFormula::Polynomial(vec![0, -3, 0, -1])));
+        assert_eq!("a(n) = 0", format!("{}", IndexFormula::Polynomial(vec![0, 0])));
+        assert_eq!("a(n) = 2*fib(n+1) -1", format!("{}", scaled(IndexFamily::Fibonacci, 2, 1, -1)));
+        assert_eq!("a(n) = -prime(n) +4", format!("{}", scaled(IndexFamily::Prime, -1, 0, 4)));
+        assert_eq!("a(n) = 3*(-2)^(n+1)", format!("{}", scaled(IndexFamily::Power(-2), 3, 1, 0)));
+        assert_eq!("a(n) = tri(n)", format!("{}", scaled(IndexFamily::Triangular, 1, 0, 0)));
+    }
+}
@@ -0,0 +1,125 @@
+use number::Number;
+use pattern::{Pattern, PatternElem};
+
+/// An iterator over the patterns in a `PatternSet`, in ascending order. Patterns are only
+/// constructed as they're requested, so finding the first (or last) one doesn't require
+/// enumerating every combination.
+pub struct PatternIter<T = i32> {
+    columns: Vec<Vec<PatternElem<T>>>,
+    front: Vec<usize>,
+    back: Vec<usize>,
+    done: bool,
+}
+
+impl<T: Number> PatternIter<T> {
+    /// Creates an iterator over the combinations of `columns`, each of which must be sorted.
+    pub(crate) fn new(columns: Vec<Vec<PatternElem<T>>>) -> Self {
+        PatternIter {
+            done: columns.iter().any(Vec::is_empty),
+            front: vec![0; columns.len()],
+            back: columns.iter().map(|column| column.len().saturating_sub(1)).collect(),
+            columns,
+        }
+    }
+
+    fn pattern_at(&self, indices: &[usize]) -> Pattern<T> {
+        self.columns.iter().zip(indices).map(|(column, &i)| column[i].clone()).collect()
+    }
+}
+
+impl<T: Number> Iterator for PatternIter<T> {
+    type Item = Pattern<T>;
+
+    fn next(&mut self) -> Option<Pattern<T>> {
+        if self.done {
+            return None;
+        }
+
+        let pat = self.pattern_at(&self.front);
+
+        if self.front == self.back {
+            self.done = true;
+            return Some(pat);
+        }
+
+        // Increments the indices like an odometer, with the last position changing fastest.
+        for (i, column) in self.front.iter_mut().zip(&self.columns).rev() {
+            if *i + 1 < column.len() {
+                *i += 1;
+                break;
+            }
+
+            *i = 0;
+        }
+
+        Some(pat)
+    }
+}
+
+impl<T: Number> DoubleEndedIterator for PatternIter<T> {
+    fn next_back(&mut self) -> Option<Pattern<T>> {
+        if self.done {
+            return None;
+        }
+
+        let pat = self.pattern_at(&self.back);
+
+        if self.front == self.back {
+            self.done = true;
+            return Some(pat);
+        }
+
+        for (i, column) in self.back.iter_mut().zip(&self.columns).rev() {
+            if *i > 0 {
+                *i -= 1;
+                break;
+            }
+
+            *i = column.len() - 1;
+        }
+
+        Some(pat)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pattern::Pattern;
+    use pattern::PatternElem::*;
+    use set::PatternSet;
+
+    #[test]
+    fn iterates_in_order() {
+        let iter = PatternSet::new(vec![vec![Plus(2), Const(1)], vec![Mult(3), Div(2), Plus(1)]]).into_iter();
+
+        assert_eq!(
+            vec![
+                pat![Const(1), Div(2)],
+                pat![Const(1), Mult(3)],
+                pat![Const(1), Plus(1)],
+                pat![Plus(2), Div(2)],
+                pat![Plus(2), Mult(3)],
+                pat![Plus(2), Plus(1)],
+            ],
+            iter.collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn iterates_from_both_ends() {
+        let mut iter = PatternSet::new(vec![vec![Plus(2), Const(1)], vec![Mult(3), Div(2)]]).into_iter();
+
+        assert_eq!(Some(pat![Plus(2), Mult(3)]), iter.next_back());
+        assert_eq!(Some(pat![Const(1), Div(2)]), iter.next());
+        assert_eq!(Some(pat![Plus(2), Div(2)]), iter.next_back());
+        assert_eq!(Some(pat![Const(1), Mult(3)]), iter.next());
+        assert_eq!(None, iter.next());
+        assert_eq!(None, iter.next_back());
+    }
+
+    #[test]
+    fn empty_column() {
+        assert_eq!(0, PatternSet::new(vec![vec![Plus(2)], vec![]]).iter().count());
+        assert_eq!(vec![Pattern::<i32>::empty()], PatternSet::new(Vec::new()).expand());
+    }
+}
@@ -1,9 +1,44 @@
+#[cfg(feature = "bigint")] extern crate num_bigint;
+#[cfg(feature = "bigint")] extern crate num_traits;
+#[cfg(feature = "serde")] #[macro_use] extern crate serde;
+
 #[macro_use] mod stepper;
 #[macro_use] mod pattern;
 mod analyzer;
+mod anomaly;
+mod approx;
+mod builder;
 mod choice;
+mod digit;
+mod error;
+mod fuzzy;
+mod index;
+mod iter;
+mod linear;
 mod meta;
+mod number;
+mod parse;
+mod rational;
+mod registry;
 mod repeat;
+#[cfg(feature = "serde")] mod serialize;
+mod set;
+mod stream;
 
 pub use analyzer::Analyzer;
-pub use pattern::{CustomPatternElem, PatternElem, Pattern};
+pub use anomaly::Anomaly;
+pub use approx::Approx;
+pub use builder::{AnalyzerBuilder, MAX_TRIAL_DIVISOR, MAX_UNCHANGED_MODULI, Operation, OverflowPolicy};
+pub use digit::{DEFAULT_RADIX, DigitOp};
+pub use error::{AnalyzerError, ParseError, ParseErrorKind, ParseNumberError};
+pub use fuzzy::{FuzzyMatch, Tolerance};
+pub use index::{IndexFamily, IndexFormula, MAX_START};
+pub use iter::PatternIter;
+pub use linear::LinearRecurrence;
+pub use meta::MAX_OPERAND_DEPTH;
+pub use number::Number;
+pub use pattern::{CustomOp, CustomPatternElem, PatternElem, Pattern};
+pub use rational::Rational;
+pub use registry::CustomRegistry;
+pub use set::PatternSet;
+pub use stream::StreamingAnalyzer;
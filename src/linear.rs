@@ -0,0 +1,179 @@
+use std::fmt::{Display, Formatter, Error};
+
+use number::Number;
+
+/// A linear recurrence `a(n) = c1 * a(n - 1) + ... + ck * a(n - k) + d`, which describes
+/// sequences like the Pell numbers (`a(n) = 2 * a(n - 1) + a(n - 2)`) that no pattern of
+/// transitions between adjacent terms can.
+///
+/// The coefficients are exact: for integer sequences they're integers, and for sequences of
+/// `Rational`s they can be fractions.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LinearRecurrence<T = i32> {
+    /// The coefficients of the previous terms, starting with the last one (`c1`).
+    pub coefficients: Vec<T>,
+    /// The constant that's added to each term (`d`).
+    pub constant: T,
+}
+
+impl<T: Number> LinearRecurrence<T> {
+    /// Returns the number of previous terms that each term depends on (`k`).
+    pub fn order(&self) -> usize {
+        self.coefficients.len()
+    }
+
+    /// Computes the term that follows `prev`, which needs at least `order()` terms.
+    pub fn next(&self, prev: &[T]) -> Option<T> {
+        if prev.len() < self.order() {
+            return None;
+        }
+
+        self.coefficients.iter().zip(prev.iter().rev()).try_fold(self.constant.clone(), |sum, (c, x)| {
+            sum.checked_add(&c.checked_mul(x)?)
+        })
+    }
+
+    /// Finds the linear recurrence of the smallest order (up to `max_order`) that describes `seq`.
+    /// Each of the `k + 1` coefficients of a recurrence of order `k` needs a term to determine it,
+    /// so to be sure that the recurrence isn't a coincidence, `seq` has to have at least `2k + 2`
+    /// terms.
+    ///
+    /// Rather than running Berlekamp-Massey over a field, the equations that each term gives are
+    /// solved directly with fraction-free elimination, so only divisions that are exact for
+    /// integers are needed. The coefficients still have to be representable by `T`, though: over
+    /// an integer type, a recurrence with fractional coefficients isn't found, even if it's the
+    /// only one. For instance, `64 32 16 8 4 2 1` follows `a(n) = a(n - 1) / 2`, which is found
+    /// for `Rational`s but not for integers. To find such recurrences, convert the sequence to
+    /// `Rational`s first.
+    ///
+    /// If the terms don't determine every coefficient (e.g. for a constant sequence, where any
+    /// `c1` works with `d = (1 - c1) * a(0)`), the undetermined ones are taken to be zero, and the
+    /// recurrence is only returned if it describes every term.
+    pub fn find(seq: &[T], max_order: usize) -> Option<Self> {
+        (1..max_order + 1).take_while(|&k| seq.len() >= 2 * k + 2)
+                          .filter_map(|k| Self::find_of_order(seq, k))
+                          .next()
+    }
+
+    fn find_of_order(seq: &[T], k: usize) -> Option<Self> {
+        // Each term after the first `k` gives an equation in the coefficients, followed by the
+        // constant: `a(n - 1) * c1 + ... + a(n - k) * ck + d = a(n)`.
+        let rows = (k..seq.len()).map(|n| {
+            let mut row: Vec<_> = seq[n - k..n].iter().rev().cloned().collect();
+            row.push(T::one());
+            row.push(seq[n].clone());
+            row
+        }).collect();
+
+        let solution = solve(rows, k + 1)?;
+        let (constant, coefficients) = solution.split_last()?;
+        let recurrence = LinearRecurrence { coefficients: coefficients.to_vec(), constant: constant.clone() };
+
+        // The elimination already rules out inconsistent equations, but checking every term keeps
+        // it honest if a computation overflowed along the way.
+        if (k..seq.len()).all(|n| recurrence.next(&seq[..n]).as_ref() == Some(&seq[n])) {
+            Some(recurrence)
+        } else {
+            None
+        }
+    }
+}
+
+/// Solves the linear equations in `rows`, each of which holds the coefficients of the `unknowns`
+/// followed by the value they add up to. Unknowns that aren't determined by the equations are set
+/// to zero. Returns `None` if there's no solution (or none that `T` can represent exactly).
+pub(crate) fn solve<T: Number>(mut rows: Vec<Vec<T>>, unknowns: usize) -> Option<Vec<T>> {
+    let mut pivots = Vec::new();
+    let mut prev_pivot = T::one();
+
+    // Bareiss's fraction-free elimination, in which each division by the previous pivot is exact.
+    for col in 0..unknowns {
+        let r = pivots.len();
+        let p = match (r..rows.len()).find(|&i| rows[i][col] != T::zero()) {
+            Some(p) => p,
+            None => continue,
+        };
+        rows.swap(r, p);
+
+        for i in r + 1..rows.len() {
+            for j in col + 1..unknowns + 1 {
+                let value = rows[r][col].checked_mul(&rows[i][j])?.checked_sub(&rows[i][col].checked_mul(&rows[r][j])?)?;
+                rows[i][j] = value.exact_div(&prev_pivot)?;
+            }
+
+            rows[i][col] = T::zero();
+        }
+
+        prev_pivot = rows[r][col].clone();
+        pivots.push(col);
+    }
+
+    // Equations left without any unknowns have to add up to zero.
+    if rows[pivots.len()..].iter().any(|row| row[unknowns] != T::zero()) {
+        return None;
+    }
+
+    let mut solution = vec![T::zero(); unknowns];
+
+    for (r, &col) in pivots.iter().enumerate().rev() {
+        let rest = (col + 1..unknowns).try_fold(T::zero(), |sum, j| sum.checked_add(&rows[r][j].checked_mul(&solution[j])?))?;
+        solution[col] = rows[r][unknowns].checked_sub(&rest)?.exact_div(&rows[r][col])?;
+    }
+
+    Some(solution)
+}
+
+/// Displays the recurrence as `linear [c1, ..., ck]`, followed by the constant if it isn't zero
+/// (e.g. `linear [2, 1] +3`).
+impl<T: Number> Display for LinearRecurrence<T> {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        write!(fmt, "linear [")?;
+
+        for (i, c) in self.coefficients.iter().enumerate() {
+            if i != 0 {
+                write!(fmt, ", ")?;
+            }
+
+            write!(fmt, "{}", c)?;
+        }
+
+        write!(fmt, "]")?;
+
+        if self.constant == T::zero() {
+            Ok(())
+        } else if self.constant.is_negative() {
+            write!(fmt, " {}", self.constant)
+        } else {
+            write!(fmt, " +{}", self.constant)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LinearRecurrence;
+
+    fn linear(coefficients: Vec<i32>, constant: i32) -> LinearRecurrence {
+        LinearRecurrence { coefficients, constant }
+    }
+
+    #[test]
+    fn find() {
+        assert_eq!(Some(linear(vec![2, 1], 0)), LinearRecurrence::find(&[0, 1, 2, 5, 12, 29, 70], 3));
+        assert_eq!(Some(linear(vec![3], -1)), LinearRecurrence::find(&[1, 2, 5, 14, 41], 3));
+        assert_eq!(Some(linear(vec![1], 0)), LinearRecurrence::find(&[5, 5, 5, 5], 3));
+        assert_eq!(Some(linear(vec![0, 1, 1], 0)), LinearRecurrence::find(&[1, 1, 1, 2, 2, 3, 4, 5, 7, 9], 3));
+        assert_eq!(None, LinearRecurrence::find(&[0, 1, 2, 5, 12], 3));
+        assert_eq!(None, LinearRecurrence::find(&[1, 2, 4, 7, 11, 17, 20, 30], 3));
+        // The only recurrences have fractional coefficients, which integers can't represent.
+        assert_eq!(None, LinearRecurrence::find(&[64, 32, 16, 8, 4, 2, 1], 3));
+    }
+
+    #[test]
+    fn fmt() {
+        assert_eq!("linear [2, 1]", format!("{}", linear(vec![2, 1], 0)));
+        assert_eq!("linear [3] -1", format!("{}", linear(vec![3], -1)));
+        assert_eq!("linear [1, -1] +4", format!("{}", linear(vec![1, -1], 4)));
+    }
+}
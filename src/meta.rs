@@ -1,54 +1,272 @@
+use std::collections::HashSet;
+
 use analyzer::Analyzer;
+use builder::{AnalyzerBuilder, Operation};
 use choice::PatternElemChoice;
-use pattern::Pattern;
+use number::Number;
+use pattern::{Pattern, PatternElem};
+use repeat::periods;
+
+/// How many levels deep the pattern describing the operands of a meta-pattern may contain
+/// meta-patterns itself when it has to be found again, e.g. for a meta-pattern that was parsed.
+/// Meta-patterns found by an analyzer keep the pattern they were found with, however deep it is.
+pub const MAX_OPERAND_DEPTH: usize = 1;
 
 #[derive(Debug)]
-pub struct MetaAnalyzer {
-    choices: Vec<PatternElemChoice>,
+pub struct MetaAnalyzer<T = i32> {
+    choices: Vec<PatternElemChoice<T>>,
+    options: AnalyzerBuilder<T>,
 }
 
-impl MetaAnalyzer {
-    pub fn new(choices: Vec<PatternElemChoice>) -> Self {
-        MetaAnalyzer { choices: choices }
+impl<T: Number> MetaAnalyzer<T> {
+    /// Creates an analyzer for meta-patterns of the given choices, which may be nested up to
+    /// `options.meta_depth` levels deep (counting the meta-patterns themselves). The operands are
+    /// described with the same operations as the choices.
+    pub fn new(choices: Vec<PatternElemChoice<T>>, options: &AnalyzerBuilder<T>) -> Self {
+        MetaAnalyzer { choices, options: options.clone() }
+    }
+
+    /// Finds a pattern describing the operands of a meta-pattern with the operations enabled by
+    /// `options`, which may itself contain meta-patterns up to `depth` levels deep. Shallower
+    /// patterns are preferred, and are looked for first since they're much cheaper to find.
+    ///
+    /// Each level describes the transitions between the operands of the level above it, so it has
+    /// one fewer term to work with, and the search ends once there are too few of them.
+    ///
+    /// Recurrences aren't used, since one of `k` terms leaves the first `k` operands unconstrained,
+    /// so a search couldn't rule out a partial meta-pattern until then.
+    pub fn operand_pattern(operands: &[T], depth: usize, options: &AnalyzerBuilder<T>) -> Option<Pattern<T>> {
+        let options = options.clone().disable(Operation::Sum).disable(Operation::Product).disable(Operation::Linear);
+
+        (0..depth + 1).filter_map(|depth| {
+            Analyzer::analyze(operands, &options.clone().meta_depth(depth)).find_any_pattern_of_length(1)
+        }).next()
+    }
+
+    /// Finds every meta-pattern made of one operation from each choice.
+    ///
+    /// The operation types of a meta-pattern have to repeat, with each type in the period showing
+    /// up at least twice (see `Pattern::has_repeating_types`); otherwise, e.g. `+1, ^2, +3, +4`
+    /// would count as repeating (with a period of three).
+    ///
+    /// Rather than trying every combination of the choices, the operations are picked one at a
+    /// time, and a partial pattern is abandoned as soon as its operation types can't repeat or
+    /// nothing could describe its operands (see `Operands`).
+    pub fn find_patterns(&self) -> Vec<Pattern<T>> {
+        // Only operations with operands can form meta-patterns. Any two operands are described by
+        // the difference between them, so it takes at least three of them to make a pattern.
+        let columns: Vec<Vec<_>> = self.choices.iter().map(|choice| {
+            let mut column: Vec<_> = choice.0.iter().filter(|elem| elem.get_operand().is_some()).cloned().collect();
+            column.sort();
+            column
+        }).collect();
+
+        if columns.len() < 3 || columns.iter().any(Vec::is_empty) {
+            return Vec::new();
+        }
+
+        // Moduli that leave an operand unchanged are bounded by the largest operand in the
+        // sequence, which isn't known until the end; using the largest one that could be picked
+        // only allows more operations, so it never rules out a valid pattern.
+        let largest = columns.iter()
+                             .flat_map(|column| column.iter().filter_map(|elem| elem.get_operand()?.checked_abs()))
+                             .max()
+                             .unwrap_or_else(T::zero);
+
+        let depth = self.options.meta_depth.saturating_sub(1);
+        let search = Search { columns: &columns };
+        let operands = Operands::new(columns.len(), depth, self.options.mod_bound(largest), &self.options);
+
+        let periods: Vec<_> = periods(columns.len()).collect();
+        let mut found = Vec::new();
+        search.extend(&mut Vec::with_capacity(columns.len()), &periods, &operands, &mut found);
+        found.sort();
+        found
     }
+}
 
-    // TODO: implement in a non-terrible way
-    pub fn find_patterns(&self) -> Vec<Pattern> {
-        let mut patterns = vec![Pattern::empty()];
+/// The state shared by each step of `MetaAnalyzer::find_patterns`.
+struct Search<'a, T: 'a> {
+    columns: &'a [Vec<PatternElem<T>>],
+}
 
-        for choice in &self.choices {
-            let mut new = Vec::new();
+impl<'a, T: Number> Search<'a, T> {
+    /// Tries each operation of the next column after `elems`, where `periods` are the periods
+    /// that the operation types of `elems` could still repeat with.
+    fn extend(&self, elems: &mut Vec<PatternElem<T>>, periods: &[usize], operands: &Operands<'a, T>,
+              found: &mut Vec<Pattern<T>>) {
+        let i = elems.len();
 
-            for pat in &mut patterns {
-                new.extend(pat.extend_each(choice.clone().into_iter()));
+        if i == self.columns.len() {
+            if let Some(pat) = operands.pattern() {
+                found.push(Pattern::with_operand_pattern(elems.clone(), pat));
             }
 
-            patterns = new;
+            return;
         }
 
-        patterns.into_iter().filter(|pat| {
-            if pat.is_empty() {
-                return false;
+        for elem in &self.columns[i] {
+            // Each chunk of a period has to have the same types as the first one.
+            let periods: Vec<_> = periods.iter()
+                                         .cloned()
+                                         .filter(|&p| i < p || elem.same_operator_type(&elems[i % p]))
+                                         .collect();
+
+            if periods.is_empty() {
+                continue;
             }
 
-            // I hate having to do a loop instead of using iterators, but there's no way to return
-            // from the outer function from a closure. I hate myself for saying this, but too bad
-            // Rust doesn't have monads...
-            let mut operands = Vec::new();
+            let next = match elem.get_operand().and_then(|operand| operands.push(&operand)) {
+                Some(next) => next,
+                None => continue,
+            };
+
+            elems.push(elem.clone());
+            self.extend(elems, &periods, &next, found);
+            elems.pop();
+        }
+    }
+}
+
+/// The operands picked so far by a search, and what could still describe them once the rest are
+/// picked: a single operation leading from each one to the next, or (up to `depth` levels deep) a
+/// meta-pattern of those operations, which is searched for alongside them in the same way. Every
+/// operation that an analyzer of the operands would find is kept, so a partial pattern is only
+/// ruled out once none of them could be extended.
+#[derive(Clone)]
+struct Operands<'a, T: 'a> {
+    /// The number of operands there will be once they've all been picked.
+    len: usize,
+    depth: usize,
+    /// A bound on the moduli for transitions that leave an operand unchanged, which is at least
+    /// the one an analyzer would use.
+    mod_bound: T,
+    options: &'a AnalyzerBuilder<T>,
+    terms: Vec<T>,
+    /// The operations leading from each operand to the next, once there are two of them.
+    ops: Option<HashSet<PatternElem<T>>>,
+    /// The meta-patterns that could still lead from each operand to the next.
+    metas: Vec<PartialMeta<'a, T>>,
+}
+
+#[derive(Clone)]
+struct PartialMeta<'a, T: 'a> {
+    elems: Vec<PatternElem<T>>,
+    periods: Vec<usize>,
+    operands: Operands<'a, T>,
+}
 
-            for elem in pat.iter() {
-                match elem.get_operand() {
-                    Some(op) => operands.push(op),
-                    None => return false,
+impl<'a, T: Number> Operands<'a, T> {
+    fn new(len: usize, depth: usize, mod_bound: T, options: &'a AnalyzerBuilder<T>) -> Self {
+        Operands { len, depth, mod_bound, options, terms: Vec::new(), ops: None, metas: Vec::new() }
+    }
+
+    /// Adds the next operand, returning `None` if nothing could describe the operands anymore.
+    fn push(&self, term: &T) -> Option<Self> {
+        let mut next = Operands {
+            terms: Vec::with_capacity(self.len),
+            ops: None,
+            metas: Vec::new(),
+            mod_bound: self.mod_bound.clone(),
+            ..*self
+        };
+
+        if let Some(prev) = self.terms.last() {
+            // Meta-patterns need at least three transitions (see `MetaAnalyzer::find_patterns`).
+            let has_metas = self.depth > 0 && self.len > 3;
+            let choice = if self.ops.is_none() || has_metas {
+                PatternElemChoice::from_pair(prev, term, &self.mod_bound, self.options).0
+            } else {
+                HashSet::new()
+            };
+
+            if has_metas {
+                let candidates: Vec<_> = choice.iter().filter(|elem| elem.get_operand().is_some()).collect();
+
+                if self.terms.len() == 1 {
+                    let start = PartialMeta::start(self);
+                    next.metas.extend(candidates.iter().filter_map(|elem| start.push(elem)));
+                } else {
+                    for meta in &self.metas {
+                        next.metas.extend(candidates.iter().filter_map(|elem| meta.push(elem)));
+                    }
                 }
             }
 
-            let analyzer = Analyzer::from_slice(&operands);
-            if analyzer.find_any_pattern_of_length(1).is_none() {
-                return false;
+            // Keeping the operations that still apply is much cheaper than finding every choice, and
+            // rules out just as many partial patterns; `pattern` checks them exactly at the end.
+            next.ops = Some(match self.ops {
+                Some(ref ops) => ops.iter().filter(|op| op.describes(prev, term)).cloned().collect(),
+                None => choice,
+            });
+
+            if next.ops.as_ref().is_some_and(HashSet::is_empty) && next.metas.is_empty() {
+                return None;
             }
+        }
+
+        next.terms.extend(self.terms.iter().cloned());
+        next.terms.push(term.clone());
+        Some(next)
+    }
+
+    /// Finds the pattern that `MetaAnalyzer::operand_pattern` would for the operands once they've
+    /// all been picked. Only the operations kept along the way are considered, but since they were
+    /// found with a looser bound on the moduli, each one is checked against the exact choices.
+    fn pattern(&self) -> Option<Pattern<T>> {
+        let largest = self.terms.iter().filter_map(Number::checked_abs).max().unwrap_or_else(T::zero);
+        let mod_bound = self.options.mod_bound(largest);
+        let choices: Vec<_> = self.terms.windows(2).map(|pair| {
+            PatternElemChoice::from_pair(&pair[0], &pair[1], &mod_bound, self.options).0
+        }).collect();
+
+        let ops = choices.iter().skip(1).fold(choices[0].clone(), |ops, choice| ops.intersection(choice).cloned().collect());
+
+        if let Some(elem) = ops.into_iter().max() {
+            return Some(Pattern::new(vec![elem]));
+        }
+
+        self.metas.iter()
+                  .filter(|meta| meta.elems.iter().zip(&choices).all(|(elem, choice)| choice.contains(elem)))
+                  .filter_map(|meta| Some(Pattern::with_operand_pattern(meta.elems.clone(), meta.operands.pattern()?)))
+                  .max()
+                  .map(|meta| Pattern::new(vec![PatternElem::Meta(meta)]))
+    }
+}
+
+impl<'a, T: Number> PartialMeta<'a, T> {
+    /// Starts a meta-pattern describing the transitions between `operands`.
+    fn start(operands: &Operands<'a, T>) -> Self {
+        // The operands of the meta-pattern are at most about twice as large as the ones it
+        // describes (e.g. the difference between them), so this bounds the moduli an analyzer
+        // would use for them.
+        let largest = operands.mod_bound.checked_add(&operands.mod_bound).unwrap_or_else(|| operands.mod_bound.clone());
+        let len = operands.len - 1;
+
+        PartialMeta {
+            elems: Vec::new(),
+            periods: periods(len).collect(),
+            operands: Operands::new(len, operands.depth - 1, operands.options.mod_bound(largest), operands.options),
+        }
+    }
+
+    /// Adds the next operation, returning `None` if its type can't repeat or nothing could
+    /// describe the operands anymore.
+    fn push(&self, elem: &PatternElem<T>) -> Option<Self> {
+        let i = self.elems.len();
+        let periods: Vec<_> = self.periods.iter()
+                                          .cloned()
+                                          .filter(|&p| i < p || elem.same_operator_type(&self.elems[i % p]))
+                                          .collect();
+
+        if periods.is_empty() {
+            return None;
+        }
+
+        let operands = self.operands.push(&elem.get_operand()?)?;
+        let mut elems = self.elems.clone();
+        elems.push(elem.clone());
 
-            pat.has_repeating_types()
-        }).collect()
+        Some(PartialMeta { elems, periods, operands })
     }
 }
@@ -0,0 +1,378 @@
+use std::convert::TryFrom;
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+
+/// The numeric types that sequences can be made of.
+///
+/// Every operation is checked: `None` is returned whenever the result can't be represented by the
+/// type, so that the analyzer can simply skip operations that don't apply rather than panicking.
+pub trait Number: Clone + Debug + Display + Eq + Hash + Ord {
+    fn zero() -> Self;
+
+    fn one() -> Self;
+
+    fn is_negative(&self) -> bool;
+
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+
+    fn checked_sub(&self, other: &Self) -> Option<Self>;
+
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+
+    /// Divides `self` by `other`, returning `None` unless the quotient is exact.
+    fn exact_div(&self, other: &Self) -> Option<Self>;
+
+    /// Finds the remainder of dividing `self` by `other`, which has the same sign as `self`.
+    fn checked_rem(&self, other: &Self) -> Option<Self>;
+
+    fn checked_abs(&self) -> Option<Self>;
+
+    fn checked_pow(&self, exp: u32) -> Option<Self> {
+        let mut result = Self::one();
+
+        for _ in 0..exp {
+            result = result.checked_mul(self)?;
+        }
+
+        Some(result)
+    }
+
+    /// Finds the `n`th root of `self`, returning `None` unless the root is exact. For even `n`,
+    /// the non-negative root is returned.
+    fn exact_root(&self, n: u32) -> Option<Self>;
+
+    /// Converts an exponent to this type, returning `None` if it can't be represented.
+    fn from_u32(n: u32) -> Option<Self>;
+
+    /// Converts `self` to an exponent, returning `None` unless it's a non-negative integer that
+    /// fits in a `u32`.
+    fn to_u32(&self) -> Option<u32>;
+
+    // Bitwise operations only apply to integers, where negative numbers are in two's complement.
+    // They return `None` by default, for types that aren't made of bits.
+
+    fn checked_xor(&self, _other: &Self) -> Option<Self> {
+        None
+    }
+
+    fn checked_and(&self, _other: &Self) -> Option<Self> {
+        None
+    }
+
+    fn checked_or(&self, _other: &Self) -> Option<Self> {
+        None
+    }
+
+    /// Flips every bit of `self`.
+    fn checked_not(&self) -> Option<Self> {
+        None
+    }
+
+    /// Shifts the bits of `self` left by `n`, returning `None` unless shifting them back gives
+    /// `self` (i.e. unless no set bits are lost).
+    fn exact_shl(&self, _n: u32) -> Option<Self> {
+        None
+    }
+
+    /// Shifts the bits of `self` right by `n`, returning `None` unless shifting them back gives
+    /// `self` (i.e. unless no set bits are lost).
+    fn exact_shr(&self, _n: u32) -> Option<Self> {
+        None
+    }
+
+    /// Rotates the bits of `self` left by `n`, for types with a fixed number of bits.
+    fn rotate_bits_left(&self, _n: u32) -> Option<Self> {
+        None
+    }
+
+    /// The number of bits of types with a fixed number of them.
+    fn bit_width() -> Option<u32> {
+        None
+    }
+}
+
+macro_rules! impl_primitive {
+    ($($t:ty),*) => {
+        $(
+            impl Number for $t {
+                fn zero() -> Self {
+                    0
+                }
+
+                fn one() -> Self {
+                    1
+                }
+
+                #[allow(unused_comparisons)]
+                fn is_negative(&self) -> bool {
+                    *self < 0
+                }
+
+                fn checked_add(&self, other: &Self) -> Option<Self> {
+                    <$t>::checked_add(*self, *other)
+                }
+
+                fn checked_sub(&self, other: &Self) -> Option<Self> {
+                    <$t>::checked_sub(*self, *other)
+                }
+
+                fn checked_mul(&self, other: &Self) -> Option<Self> {
+                    <$t>::checked_mul(*self, *other)
+                }
+
+                fn exact_div(&self, other: &Self) -> Option<Self> {
+                    match <$t>::checked_rem(*self, *other) {
+                        Some(0) => <$t>::checked_div(*self, *other),
+                        _ => None,
+                    }
+                }
+
+                fn checked_rem(&self, other: &Self) -> Option<Self> {
+                    <$t>::checked_rem(*self, *other)
+                }
+
+                fn checked_abs(&self) -> Option<Self> {
+                    if Number::is_negative(self) {
+                        <$t>::checked_sub(0, *self)
+                    } else {
+                        Some(*self)
+                    }
+                }
+
+                fn checked_pow(&self, exp: u32) -> Option<Self> {
+                    <$t>::checked_pow(*self, exp)
+                }
+
+                fn exact_root(&self, n: u32) -> Option<Self> {
+                    if n == 0 || (Number::is_negative(self) && n.is_multiple_of(2)) {
+                        return None;
+                    }
+
+                    let magnitude = if Number::is_negative(self) {
+                        (*self as i128).unsigned_abs()
+                    } else {
+                        *self as u128
+                    };
+                    let root = integer_root(magnitude, n)?;
+
+                    if Number::is_negative(self) {
+                        (root as i128).checked_neg().map(|root| root as $t)
+                    } else {
+                        Some(root as $t)
+                    }
+                }
+
+                fn from_u32(n: u32) -> Option<Self> {
+                    <$t>::try_from(n).ok()
+                }
+
+                fn to_u32(&self) -> Option<u32> {
+                    u32::try_from(*self).ok()
+                }
+
+                fn checked_xor(&self, other: &Self) -> Option<Self> {
+                    Some(*self ^ *other)
+                }
+
+                fn checked_and(&self, other: &Self) -> Option<Self> {
+                    Some(*self & *other)
+                }
+
+                fn checked_or(&self, other: &Self) -> Option<Self> {
+                    Some(*self | *other)
+                }
+
+                fn checked_not(&self) -> Option<Self> {
+                    Some(!*self)
+                }
+
+                fn exact_shl(&self, n: u32) -> Option<Self> {
+                    let shifted = <$t>::checked_shl(*self, n)?;
+                    if shifted >> n == *self { Some(shifted) } else { None }
+                }
+
+                fn exact_shr(&self, n: u32) -> Option<Self> {
+                    let shifted = <$t>::checked_shr(*self, n)?;
+                    if shifted << n == *self { Some(shifted) } else { None }
+                }
+
+                fn rotate_bits_left(&self, n: u32) -> Option<Self> {
+                    Some(<$t>::rotate_left(*self, n))
+                }
+
+                fn bit_width() -> Option<u32> {
+                    Some(<$t>::BITS)
+                }
+            }
+        )*
+    }
+}
+
+impl_primitive!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+/// Finds the exact integer `n`th root of `x` by binary search, if there is one.
+fn integer_root(x: u128, n: u32) -> Option<u128> {
+    let (mut low, mut high) = (0u128, x.min(1 << (128 / n).min(127)));
+
+    while low <= high {
+        let mid = low + (high - low) / 2;
+
+        match mid.checked_pow(n) {
+            Some(pow) if pow == x => return Some(mid),
+            Some(pow) if pow < x => low = mid + 1,
+            _ if mid == 0 => return None,
+            _ => high = mid - 1,
+        }
+    }
+
+    None
+}
+
+#[cfg(feature = "bigint")]
+mod bigint {
+    use num_bigint::{BigInt, Sign};
+    use num_traits::{Pow, Signed, ToPrimitive, Zero};
+
+    use super::Number;
+
+    impl Number for BigInt {
+        fn zero() -> Self {
+            <BigInt as Zero>::zero()
+        }
+
+        fn one() -> Self {
+            BigInt::from(1)
+        }
+
+        fn is_negative(&self) -> bool {
+            self.sign() == Sign::Minus
+        }
+
+        fn checked_add(&self, other: &Self) -> Option<Self> {
+            Some(self + other)
+        }
+
+        fn checked_sub(&self, other: &Self) -> Option<Self> {
+            Some(self - other)
+        }
+
+        fn checked_mul(&self, other: &Self) -> Option<Self> {
+            Some(self * other)
+        }
+
+        fn exact_div(&self, other: &Self) -> Option<Self> {
+            match Number::checked_rem(self, other) {
+                Some(ref rem) if rem.is_zero() => Some(self / other),
+                _ => None,
+            }
+        }
+
+        fn checked_rem(&self, other: &Self) -> Option<Self> {
+            if other.is_zero() {
+                None
+            } else {
+                Some(self % other)
+            }
+        }
+
+        fn checked_abs(&self) -> Option<Self> {
+            Some(self.abs())
+        }
+
+        fn checked_pow(&self, exp: u32) -> Option<Self> {
+            Some(Pow::pow(self, exp))
+        }
+
+        fn exact_root(&self, n: u32) -> Option<Self> {
+            if n == 0 || (Number::is_negative(self) && n.is_multiple_of(2)) {
+                return None;
+            }
+
+            let root = self.nth_root(n);
+
+            if Pow::pow(&root, n) == *self {
+                Some(root)
+            } else {
+                None
+            }
+        }
+
+        fn from_u32(n: u32) -> Option<Self> {
+            Some(BigInt::from(n))
+        }
+
+        fn to_u32(&self) -> Option<u32> {
+            ToPrimitive::to_u32(self)
+        }
+
+        fn checked_xor(&self, other: &Self) -> Option<Self> {
+            Some(self ^ other)
+        }
+
+        fn checked_and(&self, other: &Self) -> Option<Self> {
+            Some(self & other)
+        }
+
+        fn checked_or(&self, other: &Self) -> Option<Self> {
+            Some(self | other)
+        }
+
+        fn checked_not(&self) -> Option<Self> {
+            Some(!self)
+        }
+
+        fn exact_shl(&self, n: u32) -> Option<Self> {
+            Some(self << n)
+        }
+
+        fn exact_shr(&self, n: u32) -> Option<Self> {
+            let shifted = self >> n;
+            if &shifted << n == *self { Some(shifted) } else { None }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Number;
+
+    #[test]
+    fn exact_root() {
+        assert_eq!(Some(12), Number::exact_root(&144i32, 2));
+        assert_eq!(None, Number::exact_root(&143i32, 2));
+        assert_eq!(None, Number::exact_root(&-4i32, 2));
+        assert_eq!(Some(-3), Number::exact_root(&-27i64, 3));
+        assert_eq!(Some(1 << 40), Number::exact_root(&(1u128 << 120), 3));
+        assert_eq!(Some(u64::MAX as u128), Number::exact_root(&((u64::MAX as u128) * (u64::MAX as u128)), 2));
+        assert_eq!(Some(0), Number::exact_root(&0u8, 5));
+    }
+
+    #[test]
+    fn exact_div() {
+        assert_eq!(Some(3), Number::exact_div(&12i32, &4));
+        assert_eq!(None, Number::exact_div(&12i32, &5));
+        assert_eq!(None, Number::exact_div(&12i32, &0));
+        assert_eq!(None, Number::exact_div(&i32::MIN, &-1));
+    }
+
+    #[test]
+    fn exponents() {
+        assert_eq!(Some(4), 4i8.to_u32());
+        assert_eq!(None, (-4i8).to_u32());
+        assert_eq!(None, (1u64 << 40).to_u32());
+        assert_eq!(Some(200u8), <u8 as Number>::from_u32(200));
+        assert_eq!(None, <i8 as Number>::from_u32(200));
+    }
+
+    #[test]
+    fn bits() {
+        assert_eq!(Some(0b0110), Number::checked_xor(&0b1100i32, &0b1010));
+        assert_eq!(Some(-2), Number::checked_not(&1i32));
+        assert_eq!(Some(40), Number::exact_shl(&5i32, 3));
+        assert_eq!(None, Number::exact_shl(&0x40i8, 1));
+        assert_eq!(Some(-3), Number::exact_shr(&-12i32, 2));
+        assert_eq!(None, Number::exact_shr(&5i32, 1));
+        assert_eq!(None, Number::exact_shl(&1u8, 8));
+        assert_eq!(Some(0b0001_0010), Number::rotate_bits_left(&0b0010_0001u8, 4));
+    }
+}
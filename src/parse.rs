@@ -0,0 +1,562 @@
+use std::str::FromStr;
+
+use digit::{DEFAULT_RADIX, DigitOp};
+use error::{ParseError, ParseErrorKind};
+use index::{IndexFamily, IndexFormula, MAX_START};
+use linear::LinearRecurrence;
+use number::Number;
+use pattern::{Pattern, PatternElem};
+use registry::CustomRegistry;
+
+impl<T: Number + FromStr> Pattern<T> {
+    /// Parses a pattern written the way it's displayed, e.g. `+4, %6, ^2, root 2, [+1, +2...]`,
+    /// resolving the names of custom operations with `registry`. A custom name takes precedence
+    /// over a built-in operation written the same way, but can't contain commas or brackets.
+    ///
+    /// ```
+    /// # #[macro_use] extern crate sea_canal;
+    /// # use sea_canal::{CustomPatternElem, CustomRegistry, Pattern};
+    /// # use sea_canal::PatternElem::{Custom, Meta, Plus, Square};
+    /// # fn main() {
+    /// fn even(_: i32, y: i32) -> bool { y % 2 == 0 }
+    ///
+    /// let even = CustomPatternElem::new(even, "even");
+    /// let registry: CustomRegistry = vec![even.clone()].into_iter().collect();
+    /// let pat = Pattern::parse_with("^2, even, [+1, -2...]", &registry).unwrap();
+    ///
+    /// assert_eq!(pat![Square, Custom(even), Meta(pat![Plus(1), Plus(-2)])], pat);
+    /// assert_eq!("^2, even, [+1, -2...]", format!("{}", pat));
+    /// # }
+    /// ```
+    pub fn parse_with(s: &str, registry: &CustomRegistry<T>) -> Result<Self, ParseError> {
+        parse_pattern(s, 0, registry)
+    }
+}
+
+impl<T: Number + FromStr> PatternElem<T> {
+    /// Parses a single operation written the way it's displayed, like `Pattern::parse_with`.
+    pub fn parse_with(s: &str, registry: &CustomRegistry<T>) -> Result<Self, ParseError> {
+        parse_elem(s, 0, registry)
+    }
+}
+
+/// Parses a pattern without any custom operations. See `Pattern::parse_with`.
+impl<T: Number + FromStr> FromStr for Pattern<T> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        Self::parse_with(s, &CustomRegistry::new())
+    }
+}
+
+/// Parses an operation other than a custom one. See `PatternElem::parse_with`.
+impl<T: Number + FromStr> FromStr for PatternElem<T> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        Self::parse_with(s, &CustomRegistry::new())
+    }
+}
+
+fn error(kind: ParseErrorKind, token: &str, position: usize) -> ParseError {
+    ParseError { kind, token: token.to_string(), position }
+}
+
+/// Parses the comma-separated operations of `s`, which starts at byte `offset` of the whole text.
+/// Commas inside of meta-patterns are left for the meta-pattern to parse.
+fn parse_pattern<T: Number + FromStr>(s: &str, offset: usize, registry: &CustomRegistry<T>)
+                                      -> Result<Pattern<T>, ParseError> {
+    if s.trim().is_empty() {
+        return Ok(Pattern::empty());
+    }
+
+    let mut elems = Vec::new();
+    let mut start = 0;
+    let mut depth = 0;
+    let mut open = 0;
+
+    for (i, c) in s.char_indices() {
+        match c {
+            '[' => {
+                if depth == 0 {
+                    open = i;
+                }
+
+                depth += 1;
+            }
+            ']' if depth == 0 => return Err(error(ParseErrorKind::UnbalancedBracket, "]", offset + i)),
+            ']' => depth -= 1,
+            ',' if depth == 0 => {
+                elems.push(parse_elem(&s[start..i], offset + start, registry)?);
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+
+    if depth > 0 {
+        return Err(error(ParseErrorKind::UnbalancedBracket, "[", offset + open));
+    }
+
+    elems.push(parse_elem(&s[start..], offset + start, registry)?);
+    Ok(Pattern::new(elems))
+}
+
+fn parse_elem<T: Number + FromStr>(s: &str, offset: usize, registry: &CustomRegistry<T>)
+                                   -> Result<PatternElem<T>, ParseError> {
+    let trimmed = s.trim_start();
+    let offset = offset + s.len() - trimmed.len();
+    let s = trimmed.trim_end();
+
+    if s.is_empty() {
+        return Err(error(ParseErrorKind::MissingOperation, s, offset));
+    }
+
+    if let Some(custom) = registry.get(s) {
+        return Ok(PatternElem::Custom(custom.clone()));
+    }
+
+    if let Some(rest) = s.strip_prefix("a(n)").and_then(|rest| rest.trim_start().strip_prefix('=')) {
+        let rest = rest.trim_start();
+        return parse_index(rest, offset + s.len() - rest.len()).map(PatternElem::Index);
+    }
+
+    if let Some(rest) = s.strip_prefix("linear").filter(|rest| rest.starts_with(char::is_whitespace)) {
+        let rest = rest.trim_start();
+        return parse_linear(rest, offset + s.len() - rest.len()).map(PatternElem::Linear);
+    }
+
+    if s.starts_with('[') && s.ends_with(']') {
+        return parse_meta(s, offset, registry).map(PatternElem::Meta);
+    }
+
+    if let Some(rest) = s.strip_prefix("root") {
+        if rest.starts_with(char::is_whitespace) {
+            let exponent = rest.trim_start();
+            let n = parse_operand(exponent, offset + s.len() - exponent.len())?;
+
+            return PatternElem::root(n).ok_or_else(|| error(ParseErrorKind::InvalidExponent, s, offset));
+        }
+    }
+
+    if let Some(k) = parse_order(s, "sum", offset) {
+        return k.map(PatternElem::Sum);
+    }
+
+    if let Some(k) = parse_order(s, "product", offset) {
+        return k.map(PatternElem::Product);
+    }
+
+    if let Some(digit) = parse_digit(s, offset) {
+        return digit;
+    }
+
+    if let Some(bitwise) = parse_bitwise(s, offset) {
+        return bitwise;
+    }
+
+    // The rest are a symbol followed by an operand, and every symbol is a single ASCII character.
+    let operand = match s.get(1..) {
+        Some(operand) => operand,
+        None => return Err(error(ParseErrorKind::UnknownOperation, s, offset)),
+    };
+    let operand_offset = offset + 1;
+
+    match s.as_bytes()[0] {
+        b'=' => Ok(PatternElem::Const(parse_operand(operand, operand_offset)?)),
+        b'+' => Ok(PatternElem::Plus(parse_operand(operand, operand_offset)?)),
+        // Negative numbers are displayed with their own sign.
+        b'-' => Ok(PatternElem::Plus(parse_operand(s, offset)?)),
+        b'*' => Ok(PatternElem::Mult(parse_operand(operand, operand_offset)?)),
+        b'/' => Ok(PatternElem::Div(parse_operand(operand, operand_offset)?)),
+        b'%' => Ok(PatternElem::Mod(parse_operand(operand, operand_offset)?)),
+        b'^' => {
+            let n = parse_operand(operand, operand_offset)?;
+            PatternElem::pow(n).ok_or_else(|| error(ParseErrorKind::InvalidExponent, s, offset))
+        }
+        _ => Err(error(ParseErrorKind::UnknownOperation, s, offset)),
+    }
+}
+
+/// Parses a meta-pattern, written as its operations followed by an ellipsis in brackets, and
+/// optionally by `|` and the pattern describing their operands (e.g. `[+1, +2... | *2]`).
+fn parse_meta<T: Number + FromStr>(s: &str, offset: usize, registry: &CustomRegistry<T>)
+                                   -> Result<Pattern<T>, ParseError> {
+    let inner = &s[1..s.len() - 1];
+    let mut depth = 0;
+    let mut ellipsis = None;
+
+    for (i, c) in inner.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            '.' if depth == 0 && inner[i..].starts_with("...") => {
+                ellipsis = Some(i);
+                break;
+            }
+            _ => (),
+        }
+    }
+
+    let (elems, rest) = match ellipsis {
+        Some(i) => (&inner[..i], &inner[i + 3..]),
+        None => return Err(error(ParseErrorKind::MissingEllipsis, s, offset)),
+    };
+    let pat = parse_pattern(elems, offset + 1, registry)?;
+
+    if rest.trim().is_empty() {
+        return Ok(pat);
+    }
+
+    match rest.trim_start().strip_prefix('|') {
+        Some(operands) => {
+            let operands = parse_pattern(operands, offset + s.len() - 1 - operands.len(), registry)?;
+            Ok(Pattern::with_operand_pattern(pat.into_iter().collect(), operands))
+        }
+        None => Err(error(ParseErrorKind::MissingEllipsis, s, offset)),
+    }
+}
+
+/// Parses the coefficients and constant of a linear recurrence, written as `[c1, ..., ck] +d`
+/// (where the constant is optional).
+fn parse_linear<T: Number + FromStr>(s: &str, offset: usize) -> Result<LinearRecurrence<T>, ParseError> {
+    let end = match (s.starts_with('['), s.find(']')) {
+        (true, Some(end)) => end,
+        _ => return Err(error(ParseErrorKind::UnbalancedBracket, s, offset)),
+    };
+
+    let mut coefficients = Vec::new();
+    let mut start = 1;
+
+    for part in s[1..end].split(',') {
+        let trimmed = part.trim_start();
+        let position = offset + start + part.len() - trimmed.len();
+
+        if trimmed.trim_end().is_empty() {
+            return Err(error(ParseErrorKind::MissingOperation, "", position));
+        }
+
+        coefficients.push(parse_operand(trimmed.trim_end(), position)?);
+        start += part.len() + 1;
+    }
+
+    let constant = s[end + 1..].trim_start();
+    let position = offset + s.len() - constant.len();
+    let constant = match constant.as_bytes().first() {
+        None => T::zero(),
+        Some(b'+') => parse_operand(&constant[1..], position + 1)?,
+        Some(b'-') => parse_operand(constant, position)?,
+        Some(_) => return Err(error(ParseErrorKind::InvalidOperand, constant, position)),
+    };
+
+    Ok(LinearRecurrence { coefficients, constant })
+}
+
+/// A term of an index formula, apart from its coefficient.
+enum IndexTerm<T> {
+    /// `n^d`, where a constant is `n^0`.
+    Power(usize),
+    /// A family evaluated at `n` plus a start.
+    Family(IndexFamily<T>, usize),
+}
+
+/// Parses the right-hand side of an index formula, written as terms preceded by their signs
+/// (e.g. `2*n^2 -n +1` or `3*fib(n+1) -2`). Either all of the terms are powers of `n`, or one
+/// of them is a family and the rest are constants.
+fn parse_index<T: Number + FromStr>(s: &str, offset: usize) -> Result<IndexFormula<T>, ParseError> {
+    if s.is_empty() {
+        return Err(error(ParseErrorKind::MissingOperation, s, offset));
+    }
+
+    let mut powers = vec![T::zero()];
+    let mut family = None;
+
+    for (i, token) in s.split_whitespace().enumerate() {
+        let position = offset + token.as_ptr() as usize - s.as_ptr() as usize;
+
+        match parse_index_term(token, i == 0, position)? {
+            (c, IndexTerm::Power(degree)) => {
+                if powers.len() <= degree {
+                    powers.resize(degree + 1, T::zero());
+                }
+
+                powers[degree] = powers[degree].checked_add(&c)
+                                               .ok_or_else(|| error(ParseErrorKind::InvalidOperand, token, position))?;
+            }
+            (_, IndexTerm::Family(..)) if family.is_some() => {
+                return Err(error(ParseErrorKind::UnknownOperation, token, position));
+            }
+            (scale, IndexTerm::Family(f, start)) => family = Some((f, scale, start)),
+        }
+    }
+
+    match family {
+        None => Ok(IndexFormula::Polynomial(powers)),
+        Some(_) if powers.len() > 1 => Err(error(ParseErrorKind::UnknownOperation, s, offset)),
+        Some((family, scale, start)) => Ok(IndexFormula::Scaled { family, scale, start, offset: powers.remove(0) }),
+    }
+}
+
+/// Parses a term of an index formula, which is either a constant or an optional coefficient
+/// (followed by `*`) and an `IndexTerm`. Terms other than the first have to start with their sign.
+fn parse_index_term<T: Number + FromStr>(token: &str, first: bool, offset: usize)
+                                         -> Result<(T, IndexTerm<T>), ParseError> {
+    let (negative, rest) = match token.as_bytes()[0] {
+        b'+' => (false, &token[1..]),
+        b'-' => (true, &token[1..]),
+        _ if first => (false, token),
+        _ => return Err(error(ParseErrorKind::UnknownOperation, token, offset)),
+    };
+    let rest_offset = offset + token.len() - rest.len();
+
+    let (c, term) = match rest.find('*') {
+        Some(i) => {
+            let term = parse_index_base(&rest[i + 1..]).ok_or_else(|| error(ParseErrorKind::UnknownOperation, token, offset))?;
+            (parse_operand(&rest[..i], rest_offset)?, term)
+        }
+        None => match parse_index_base(rest) {
+            Some(term) => (T::one(), term),
+            None => (parse_operand(rest, rest_offset)?, IndexTerm::Power(0)),
+        },
+    };
+
+    let c = if negative {
+        T::zero().checked_sub(&c).ok_or_else(|| error(ParseErrorKind::InvalidOperand, token, offset))?
+    } else {
+        c
+    };
+
+    Ok((c, term))
+}
+
+/// Parses `n`, `n^d`, a family such as `fib(n+1)`, or a power such as `(-2)^n`.
+fn parse_index_base<T: Number + FromStr>(s: &str) -> Option<IndexTerm<T>> {
+    if s == "n" {
+        return Some(IndexTerm::Power(1));
+    }
+
+    if let Some(degree) = s.strip_prefix("n^") {
+        return degree.parse().ok().filter(|&d| d > 0).map(IndexTerm::Power);
+    }
+
+    let families = [("fact", IndexFamily::Factorial), ("fib", IndexFamily::Fibonacci), ("prime", IndexFamily::Prime),
+                    ("tri", IndexFamily::Triangular)];
+
+    for (name, family) in families.iter() {
+        if let Some(n) = s.strip_prefix(name).filter(|n| n.starts_with('(')) {
+            return parse_index_start(n).map(|start| IndexTerm::Family(family.clone(), start));
+        }
+    }
+
+    let (base, n) = s.split_at(s.rfind('^')?);
+    let base = base.strip_prefix('(').and_then(|b| b.strip_suffix(')')).unwrap_or(base);
+    let start = parse_index_start(&n[1..])?;
+
+    Some(IndexTerm::Family(IndexFamily::Power(base.parse().ok()?), start))
+}
+
+/// Parses the index that a family is evaluated at, which is `n` or `n+s`, possibly in parentheses,
+/// where `s` is at most `MAX_START`.
+fn parse_index_start(s: &str) -> Option<usize> {
+    let inner = s.strip_prefix('(').and_then(|s| s.strip_suffix(')')).unwrap_or(s);
+
+    match inner.strip_prefix("n+") {
+        Some(start) => start.parse().ok().filter(|&start| start <= MAX_START),
+        None if inner == "n" => Some(0),
+        None => None,
+    }
+}
+
+/// Parses a digit operation, e.g. `digit sum` or `concat 7 (base 2)`, if `s` is one.
+fn parse_digit<T: Number + FromStr>(s: &str, offset: usize) -> Option<Result<PatternElem<T>, ParseError>> {
+    let (op, radix) = match s.strip_suffix(')').and_then(|rest| rest.rsplit_once(" (base ")) {
+        Some((op, radix)) => (op.trim_end(), Some(radix)),
+        None => (s, None),
+    };
+
+    let parsed = match op {
+        "digit count" => Ok(DigitOp::Count),
+        "digit product" => Ok(DigitOp::Product),
+        "digit sum" => Ok(DigitOp::Sum),
+        "+digit sum" => Ok(DigitOp::PlusSum),
+        "reverse" => Ok(DigitOp::Reverse),
+        _ => {
+            let (name, operand) = op.split_once(char::is_whitespace)?;
+            let operand = operand.trim_start();
+            let position = offset + op.len() - operand.len();
+
+            match name {
+                "concat" => parse_operand(operand, position).and_then(|c: T| {
+                    DigitOp::Concat(T::zero()).with_operand(c).ok_or_else(|| error(ParseErrorKind::InvalidOperand, operand, position))
+                }),
+                "rotate" => match parse_operand(operand, position) {
+                    Ok(0) => Err(error(ParseErrorKind::InvalidOperand, operand, position)),
+                    k => k.map(DigitOp::Rotate),
+                },
+                _ => return None,
+            }
+        }
+    };
+
+    let radix = match radix {
+        Some(radix) => {
+            let position = offset + s.len() - radix.len() - 1;
+            parse_operand(radix, position)
+        }
+        None => Ok(DEFAULT_RADIX),
+    };
+
+    Some(parsed.and_then(|op| radix.map(|radix| PatternElem::Digit(op, radix))))
+}
+
+/// Parses a bitwise operation, e.g. `xor 5` or `<<2`, if `s` is one.
+fn parse_bitwise<T: Number + FromStr>(s: &str, offset: usize) -> Option<Result<PatternElem<T>, ParseError>> {
+    let (elem, operand) = if let Some(n) = s.strip_prefix("<<") {
+        (PatternElem::ShiftLeft(T::zero()), n)
+    } else if let Some(n) = s.strip_prefix(">>") {
+        (PatternElem::ShiftRight(T::zero()), n)
+    } else {
+        let (name, operand) = s.split_once(char::is_whitespace)?;
+
+        let elem = match name {
+            "and" => PatternElem::And(T::zero()),
+            "or" => PatternElem::Or(T::zero()),
+            "rotl" => PatternElem::RotateLeft(T::zero()),
+            "xor" => PatternElem::Xor(T::zero()),
+            _ => return None,
+        };
+
+        (elem, operand.trim_start())
+    };
+
+    // Shifts and rotations are checked the same way as when their operands are replaced.
+    let position = offset + s.len() - operand.len();
+    Some(parse_operand(operand, position)
+        .and_then(|n| elem.with_operand(n).ok_or_else(|| error(ParseErrorKind::InvalidOperand, operand, position))))
+}
+
+/// Parses the number of terms `k` of a recurrence written as `name k`, if `s` is one.
+fn parse_order(s: &str, name: &str, offset: usize) -> Option<Result<usize, ParseError>> {
+    let order = s.strip_prefix(name).filter(|rest| rest.starts_with(char::is_whitespace))?.trim_start();
+    let offset = offset + s.len() - order.len();
+
+    Some(match parse_operand(order, offset) {
+        Ok(k) if k < 2 => Err(error(ParseErrorKind::InvalidOperand, order, offset)),
+        k => k,
+    })
+}
+
+fn parse_operand<T: FromStr>(s: &str, offset: usize) -> Result<T, ParseError> {
+    s.parse().map_err(|_| error(ParseErrorKind::InvalidOperand, s, offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use digit::DigitOp;
+    use error::{ParseError, ParseErrorKind};
+    use index::{IndexFamily, IndexFormula};
+    use linear::LinearRecurrence;
+    use pattern::{Pattern, PatternElem};
+    use pattern::PatternElem::*;
+
+    fn parse_error(s: &str) -> ParseError {
+        s.parse::<Pattern>().unwrap_err()
+    }
+
+    #[test]
+    fn parse_elems() {
+        assert_eq!(Ok(Const(-3)), "=-3".parse());
+        assert_eq!(Ok(Plus(4)), "+4".parse());
+        assert_eq!(Ok(Plus(-4)), "-4".parse());
+        assert_eq!(Ok(Mult(-4)), "*-4".parse());
+        assert_eq!(Ok(Div(2)), "/2".parse());
+        assert_eq!(Ok(Mod(-6)), "%-6".parse());
+        assert_eq!(Ok(PatternElem::<i32>::Square), "^2".parse());
+        assert_eq!(Ok(PatternElem::<i32>::Cube), "^3".parse());
+        assert_eq!(Ok(Pow(5)), "^5".parse());
+        assert_eq!(Ok(PatternElem::<i32>::SquareRoot), "root 2".parse());
+        assert_eq!(Ok(PatternElem::<i32>::CubeRoot), "root 3".parse());
+        assert_eq!(Ok(Root(7)), "root  7".parse());
+        assert_eq!(Ok(PatternElem::<i32>::Sum(2)), "sum 2".parse());
+        assert_eq!(Ok(PatternElem::<i32>::Product(3)), "product 3".parse());
+        assert_eq!(Ok(Digit(DigitOp::<i32>::Sum, 10)), "digit sum".parse());
+        assert_eq!(Ok(Digit(DigitOp::Concat(7), 2)), "concat 7 (base 2)".parse());
+        assert_eq!(Ok(Xor(5)), "xor 5".parse());
+        assert_eq!(Ok(And(-3)), "and -3".parse());
+        assert_eq!(Ok(ShiftLeft(2)), "<<2".parse());
+        assert_eq!(Ok(ShiftRight(1)), ">>1".parse());
+        assert_eq!(Ok(Index(IndexFormula::Polynomial(vec![1, 0, 2]))), "a(n) = 2*n^2 +1".parse());
+        assert_eq!(Ok(Index(IndexFormula::Scaled { family: IndexFamily::Triangular, scale: 1, start: 0, offset: 0 })),
+                   "a(n)=tri(n)".parse());
+    }
+
+    #[test]
+    fn parse_round_trip() {
+        let pat = pat![Plus(4), Mod(-6), Square, SquareRoot, Meta(pat![Plus(1), Meta(pat![Mult(2), Const(0)])]), Root(4),
+                       Sum(3), Product(2), Linear(LinearRecurrence { coefficients: vec![2, -1], constant: -3 }),
+                       Linear(LinearRecurrence { coefficients: vec![1], constant: 0 }),
+                       Index(IndexFormula::Polynomial(vec![1, -3, 0, 1])), Index(IndexFormula::Polynomial(vec![0, 0, -2])),
+                       Index(IndexFormula::Scaled { family: IndexFamily::Fibonacci, scale: 2, start: 1, offset: -1 }),
+                       Index(IndexFormula::Scaled { family: IndexFamily::Power(-2), scale: -1, start: 0, offset: 3 }),
+                       Index(IndexFormula::Scaled { family: IndexFamily::Prime, scale: 1, start: 2, offset: 0 }),
+                       Digit(DigitOp::PlusSum, 10), Digit(DigitOp::Concat(12), 10), Digit(DigitOp::Rotate(2), 2),
+                       Digit(DigitOp::Reverse, 16), Digit(DigitOp::Count, 10), Xor(6), And(-2), Or(8), ShiftLeft(3),
+                       ShiftRight(1), RotateLeft(4)];
+
+        assert_eq!(Ok(pat.clone()), format!("{}", pat).parse());
+        assert_eq!(Ok(Pattern::empty()), "".parse::<Pattern>());
+        assert_eq!(Ok(pat![Plus(1), Plus(2)]), " +1 ,+2 ".parse());
+
+        // Operands are only written when they aren't the ones that would be found by default.
+        let pat: Pattern = "[+2, +4, +8... | +2, [+2, +4...]], [+1, +2, +3... | +1]".parse().unwrap();
+        assert_eq!("[+2, +4, +8... | +2, [+2, +4...]], [+1, +2, +3...]", format!("{}", pat));
+        assert_eq!(Ok(pat.clone()), format!("{}", pat).parse());
+        assert!(pat != pat![Meta(pat![Plus(2), Plus(4), Plus(8)]), Meta(pat![Plus(1), Plus(2), Plus(3)])]);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(ParseError { kind: ParseErrorKind::UnknownOperation, token: "foo".to_string(), position: 4 },
+                   parse_error("+1, foo, +2"));
+        assert_eq!(ParseError { kind: ParseErrorKind::InvalidOperand, token: "x".to_string(), position: 6 },
+                   parse_error("+1, [*x...]"));
+        assert_eq!(ParseError { kind: ParseErrorKind::MissingOperation, token: String::new(), position: 3 },
+                   parse_error("+1,, +2"));
+        assert_eq!(ParseError { kind: ParseErrorKind::InvalidExponent, token: "root 1".to_string(), position: 0 },
+                   parse_error("root 1"));
+        assert_eq!(ParseError { kind: ParseErrorKind::InvalidExponent, token: "^-2".to_string(), position: 0 },
+                   parse_error("^-2"));
+        assert_eq!(ParseError { kind: ParseErrorKind::InvalidOperand, token: "1".to_string(), position: 4 },
+                   parse_error("sum 1"));
+        assert_eq!(ParseError { kind: ParseErrorKind::UnbalancedBracket, token: "[".to_string(), position: 4 },
+                   parse_error("+1, [+1, +2..."));
+        assert_eq!(ParseError { kind: ParseErrorKind::UnbalancedBracket, token: "]".to_string(), position: 9 },
+                   parse_error("+1, +1...]"));
+        assert_eq!(ParseError { kind: ParseErrorKind::MissingEllipsis, token: "[+1, +2]".to_string(), position: 0 },
+                   parse_error("[+1, +2]"));
+        assert_eq!(ParseError { kind: ParseErrorKind::MissingEllipsis, token: "[+1, +2... *2]".to_string(), position: 0 },
+                   parse_error("[+1, +2... *2]"));
+        assert_eq!(ParseError { kind: ParseErrorKind::InvalidOperand, token: "x".to_string(), position: 12 },
+                   parse_error("linear [2,  x]"));
+        assert_eq!(ParseError { kind: ParseErrorKind::UnknownOperation, token: "3".to_string(), position: 11 },
+                   parse_error("a(n) = n^2 3"));
+        assert_eq!(ParseError { kind: ParseErrorKind::UnknownOperation, token: "fib(n) +n".to_string(), position: 7 },
+                   parse_error("a(n) = fib(n) +n"));
+        assert_eq!(ParseError { kind: ParseErrorKind::InvalidOperand, token: "x".to_string(), position: 8 },
+                   parse_error("a(n) = -x*n"));
+        assert_eq!(ParseErrorKind::InvalidOperand, parse_error("a(n) = prime(n+200000)").kind);
+        assert_eq!(ParseError { kind: ParseErrorKind::InvalidOperand, token: "-1".to_string(), position: 7 },
+                   parse_error("concat -1"));
+        assert_eq!(ParseError { kind: ParseErrorKind::InvalidOperand, token: "x".to_string(), position: 14 },
+                   parse_error("reverse (base x)"));
+        assert_eq!(ParseError { kind: ParseErrorKind::InvalidOperand, token: "0".to_string(), position: 6 },
+                   parse_error("+1, <<0"));
+        assert_eq!(ParseError { kind: ParseErrorKind::InvalidOperand, token: "x".to_string(), position: 4 },
+                   parse_error("xor x"));
+        assert_eq!(ParseError { kind: ParseErrorKind::UnknownOperation, token: "é".to_string(), position: 0 },
+                   parse_error("é"));
+        assert_eq!(ParseError { kind: ParseErrorKind::UnknownOperation, token: "é".to_string(), position: 4 },
+                   parse_error("+1, é"));
+        assert_eq!("unknown operation `foo` at position 4", format!("{}", parse_error("+1, foo")));
+    }
+}
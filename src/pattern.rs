@@ -1,104 +1,501 @@
-use std::fmt::{Display, Formatter, Error};
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::fmt::{Debug, Display, Formatter, Error};
+use std::hash::{Hash, Hasher};
 use std::iter::FromIterator;
+use std::mem;
 use std::slice::Iter;
+use std::sync::Arc;
+
+use anomaly::Anomaly;
+use builder::AnalyzerBuilder;
+use digit::{DigitOp, fmt_digit_op};
+use index::{IndexFamily, IndexFormula};
+use linear::LinearRecurrence;
+use meta::{MAX_OPERAND_DEPTH, MetaAnalyzer};
+use number::Number;
+use repeat::{is_repeating_with_predicate, period_with_predicate};
+
+/// The cost of describing an operation as a meta-pattern, on top of its operations and operands.
+const META_COST: usize = 3;
+
+/// The length of an operand's magnitude as displayed; its sign comes for free, so that e.g. `-2`
+/// is as simple as `+2`.
+fn operand_cost<T: Number>(operand: &T) -> usize {
+    operand.checked_abs().unwrap_or_else(|| operand.clone()).to_string().len()
+}
 
-use repeat::is_repeating_with_predicate;
-
-/// Operations from one integer to another.
+/// Operations from one number to another.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
-pub enum PatternElem {
+pub enum PatternElem<T = i32> {
     // Listed alphabetically to make equality sorting intuitive.
-    Const(i32),
+    /// The bitwise and with a mask (see `Number` for how negative numbers are treated).
+    And(T),
+    Const(T),
     Cube,
     CubeRoot,
-    Custom(CustomPatternElem),
-    Div(i32),
-    Meta(Pattern),
-    Mod(i32),
-    Mult(i32),
-    Plus(i32),
+    Custom(CustomPatternElem<T>),
+    /// Operates on the digits of a number written in the given radix (see `DigitOp`).
+    Digit(DigitOp<T>, u32),
+    Div(T),
+    /// Computes the term from its index alone, ignoring the terms before it (see `IndexFormula`).
+    Index(IndexFormula<T>),
+    /// Computes the next term from several previous ones (see `LinearRecurrence`).
+    Linear(LinearRecurrence<T>),
+    Meta(Pattern<T>),
+    Mod(T),
+    Mult(T),
+    Or(T),
+    Plus(T),
+    /// Raises a number to a power of at least 4; smaller powers are `Square` and `Cube`.
+    Pow(T),
+    /// Multiplies the previous `k` terms (where `k` is at least 2), rather than operating on the
+    /// previous term alone.
+    Product(usize),
+    /// Takes the root of a degree of at least 4; smaller degrees are `SquareRoot` and `CubeRoot`.
+    Root(T),
+    /// Rotates the bits of a number of a fixed width left by at least 1.
+    RotateLeft(T),
+    /// Shifts the bits of a number left by at least 1, without losing any.
+    ShiftLeft(T),
+    /// Shifts the bits of a number right by at least 1, without losing any.
+    ShiftRight(T),
     Square,
     SquareRoot,
+    /// Adds up the previous `k` terms (where `k` is at least 2), e.g. `Sum(2)` for the Fibonacci
+    /// numbers.
+    Sum(usize),
+    Xor(T),
 }
 
-impl PatternElem {
-    pub fn get_operand(&self) -> Option<i32> {
+impl<T: Number> PatternElem<T> {
+    /// Returns the numerical parameter of the operation, which for powers and roots is the
+    /// exponent (e.g. 2 for `Square`).
+    pub fn get_operand(&self) -> Option<T> {
+        match *self {
+            PatternElem::Plus(ref i) | PatternElem::Mult(ref i) |
+            PatternElem::Div(ref i) | PatternElem::Mod(ref i) |
+            PatternElem::Pow(ref i) | PatternElem::Root(ref i) |
+            PatternElem::And(ref i) | PatternElem::Or(ref i) | PatternElem::Xor(ref i) |
+            PatternElem::ShiftLeft(ref i) | PatternElem::ShiftRight(ref i) |
+            PatternElem::RotateLeft(ref i) => Some(i.clone()),
+            PatternElem::Square | PatternElem::SquareRoot => T::from_u32(2),
+            PatternElem::Cube | PatternElem::CubeRoot => T::from_u32(3),
+            PatternElem::Digit(ref op, _) => op.get_operand(),
+            _ => None
+        }
+    }
+
+    /// Returns an operation of the same type as this one but with a different operand, or `None`
+    /// if this type of operation doesn't take an operand (or can't take that one, e.g. a power
+    /// with an exponent that isn't an integer greater than 1).
+    pub fn with_operand(&self, operand: T) -> Option<Self> {
         match *self {
-            PatternElem::Plus(i) | PatternElem::Mult(i) |
-            PatternElem::Div(i) | PatternElem::Mod(i) => Some(i),
+            PatternElem::Plus(_) => Some(PatternElem::Plus(operand)),
+            PatternElem::Mult(_) => Some(PatternElem::Mult(operand)),
+            PatternElem::Div(_) => Some(PatternElem::Div(operand)),
+            PatternElem::Mod(_) => Some(PatternElem::Mod(operand)),
+            PatternElem::Square | PatternElem::Cube | PatternElem::Pow(_) => PatternElem::pow(operand),
+            PatternElem::SquareRoot | PatternElem::CubeRoot | PatternElem::Root(_) => PatternElem::root(operand),
+            PatternElem::Digit(ref op, radix) => Some(PatternElem::Digit(op.with_operand(operand)?, radix)),
+            PatternElem::And(_) => Some(PatternElem::And(operand)),
+            PatternElem::Or(_) => Some(PatternElem::Or(operand)),
+            PatternElem::Xor(_) => Some(PatternElem::Xor(operand)),
+            PatternElem::ShiftLeft(_) => PatternElem::places(operand).map(PatternElem::ShiftLeft),
+            PatternElem::ShiftRight(_) => PatternElem::places(operand).map(PatternElem::ShiftRight),
+            PatternElem::RotateLeft(_) => PatternElem::places(operand).map(PatternElem::RotateLeft),
             _ => None
         }
     }
 
+    /// Checks the number of places that bits are shifted or rotated by, which must be a positive
+    /// integer.
+    fn places(n: T) -> Option<T> {
+        match n.to_u32()? {
+            0 => None,
+            _ => Some(n),
+        }
+    }
+
+    /// Constructs the operation raising a number to the power `n`, which must be an integer
+    /// greater than 1.
+    pub fn pow(n: T) -> Option<Self> {
+        match n.to_u32()? {
+            0 | 1 => None,
+            2 => Some(PatternElem::Square),
+            3 => Some(PatternElem::Cube),
+            _ => Some(PatternElem::Pow(n)),
+        }
+    }
+
+    /// Constructs the operation taking the `n`th root of a number, where `n` must be an integer
+    /// greater than 1.
+    pub fn root(n: T) -> Option<Self> {
+        match n.to_u32()? {
+            0 | 1 => None,
+            2 => Some(PatternElem::SquareRoot),
+            3 => Some(PatternElem::CubeRoot),
+            _ => Some(PatternElem::Root(n)),
+        }
+    }
+
+    /// Applies the operation to `x`, yielding the next term of a sequence. Returns `None` if the
+    /// result can't be represented by `T`, or if it can't be computed from `x` alone (i.e. for
+    /// meta-patterns, which depend on their position; see `Pattern::elem_at`, for recurrences and
+    /// index formulas, which depend on earlier terms or their number; see `apply_after`, and for
+    /// custom elements that weren't given an `apply` function).
+    pub fn apply(&self, x: &T) -> Option<T> {
+        match *self {
+            PatternElem::Const(ref i) => Some(i.clone()),
+            PatternElem::Plus(ref i) => x.checked_add(i),
+            PatternElem::Mult(ref i) => x.checked_mul(i),
+            PatternElem::Div(ref i) => x.exact_div(i),
+            PatternElem::Mod(ref i) => x.checked_rem(i),
+            PatternElem::Square => x.checked_pow(2),
+            PatternElem::Cube => x.checked_pow(3),
+            PatternElem::SquareRoot => x.exact_root(2),
+            PatternElem::CubeRoot => x.exact_root(3),
+            PatternElem::Pow(ref n) => x.checked_pow(n.to_u32()?),
+            PatternElem::Root(ref n) => x.exact_root(n.to_u32()?),
+            PatternElem::Custom(ref custom) => custom.apply(x.clone()),
+            PatternElem::Digit(ref op, radix) => op.apply(x, radix),
+            PatternElem::And(ref m) => x.checked_and(m),
+            PatternElem::Or(ref m) => x.checked_or(m),
+            PatternElem::Xor(ref m) => x.checked_xor(m),
+            PatternElem::ShiftLeft(ref n) => x.exact_shl(n.to_u32()?),
+            PatternElem::ShiftRight(ref n) => x.exact_shr(n.to_u32()?),
+            PatternElem::RotateLeft(ref n) => x.rotate_bits_left(n.to_u32()?),
+            PatternElem::Index(_) | PatternElem::Linear(_) | PatternElem::Meta(_) | PatternElem::Product(_) |
+            PatternElem::Sum(_) => None,
+        }
+    }
+
+    /// Returns the number of terms before the next one that the operation depends on, which is 1
+    /// for everything but recurrences.
+    pub fn order(&self) -> usize {
+        match *self {
+            PatternElem::Product(k) | PatternElem::Sum(k) => k,
+            PatternElem::Linear(ref linear) => linear.order(),
+            _ => 1,
+        }
+    }
+
+    /// Same as `apply`, but given the terms that come before the next one (of which only the last
+    /// `order()` are used), so that recurrences can be computed too. Index formulas compute the
+    /// term whose index is the number of terms in `prev`, so it has to hold all of them.
+    pub fn apply_after(&self, prev: &[T]) -> Option<T> {
+        match *self {
+            PatternElem::Product(k) if k <= prev.len() => {
+                prev[prev.len() - k..].iter().try_fold(T::one(), |product, x| product.checked_mul(x))
+            }
+            PatternElem::Sum(k) if k <= prev.len() => {
+                prev[prev.len() - k..].iter().try_fold(T::zero(), |sum, x| sum.checked_add(x))
+            }
+            PatternElem::Product(_) | PatternElem::Sum(_) => None,
+            PatternElem::Linear(ref linear) => linear.next(prev),
+            PatternElem::Index(ref formula) => formula.term(prev.len()),
+            _ => self.apply(prev.last()?),
+        }
+    }
+
+    /// Same as `describes`, but given the terms that come before `y`. A recurrence describes any
+    /// term without enough terms before it, since those are the ones it starts from.
+    pub fn describes_after(&self, prev: &[T], y: &T) -> bool {
+        match *self {
+            PatternElem::Product(_) | PatternElem::Sum(_) | PatternElem::Linear(_) => {
+                prev.len() < self.order() || self.apply_after(prev).as_ref() == Some(y)
+            }
+            PatternElem::Index(ref formula) => formula.term(prev.len()).as_ref() == Some(y),
+            _ => prev.last().is_some_and(|x| self.describes(x, y)),
+        }
+    }
+
+    /// Determines whether the operation leads from `x` to `y`. Unlike comparing the result of
+    /// `apply`, this works for custom elements without an `apply` function. Meta-patterns and
+    /// recurrences don't describe any transition by themselves (see `Pattern::elem_at` and
+    /// `describes_after`).
+    pub fn describes(&self, x: &T, y: &T) -> bool {
+        match *self {
+            PatternElem::Custom(ref custom) => custom.check(x.clone(), y.clone()),
+            _ => self.apply(x).as_ref() == Some(y),
+        }
+    }
+
+    /// Estimates how complex the operation is to describe, so that simpler explanations of a
+    /// sequence can be preferred. Each type of operation has a fixed cost, to which the length of
+    /// its operand (as displayed, without its sign) is added; index formulas and recurrences pay
+    /// for each of theirs that isn't trivial. A meta-pattern costs as much as the
+    /// pattern that describes its operands, plus the operation types that it repeats.
+    pub fn cost(&self) -> usize {
+        match *self {
+            PatternElem::Plus(ref i) => 1 + operand_cost(i),
+            PatternElem::Mult(ref i) | PatternElem::Div(ref i) => 2 + operand_cost(i),
+            PatternElem::Mod(ref i) => 3 + operand_cost(i),
+            PatternElem::Const(ref i) => 4 + operand_cost(i),
+            PatternElem::Pow(ref i) => 3 + operand_cost(i),
+            PatternElem::Root(ref i) => 4 + operand_cost(i),
+            PatternElem::ShiftLeft(ref i) | PatternElem::ShiftRight(ref i) | PatternElem::And(ref i) |
+            PatternElem::Or(ref i) | PatternElem::Xor(ref i) | PatternElem::RotateLeft(ref i) => {
+                self.base_cost() + operand_cost(i)
+            }
+            PatternElem::Sum(k) | PatternElem::Product(k) => self.base_cost() + k.to_string().len(),
+            PatternElem::Digit(ref op, _) => self.base_cost() + op.get_operand().as_ref().map_or(0, operand_cost),
+            PatternElem::Linear(ref linear) => {
+                let constant = if linear.constant == T::zero() { 0 } else { operand_cost(&linear.constant) };
+                self.base_cost() + constant + linear.coefficients.iter().map(operand_cost).sum::<usize>()
+            }
+            PatternElem::Index(IndexFormula::Polynomial(ref coefficients)) => {
+                self.base_cost() + coefficients.iter().filter(|&c| *c != T::zero()).map(operand_cost).sum::<usize>()
+            }
+            PatternElem::Index(IndexFormula::Scaled { ref family, ref scale, start, ref offset }) => {
+                let base = match *family {
+                    IndexFamily::Power(ref b) => operand_cost(b),
+                    _ => 0,
+                };
+                let scale = if *scale == T::one() { 0 } else { operand_cost(scale) };
+                let offset = if *offset == T::zero() { 0 } else { operand_cost(offset) };
+                let start = if start == 0 { 0 } else { start.to_string().len() };
+
+                self.base_cost() + base + scale + offset + start
+            }
+            PatternElem::Meta(ref pat) => pat.meta_cost(),
+            _ => self.base_cost(),
+        }
+    }
+
+    /// The cost of the operation type alone, ignoring its operand.
+    fn base_cost(&self) -> usize {
+        match *self {
+            PatternElem::Plus(_) => 1,
+            PatternElem::Mult(_) | PatternElem::Div(_) | PatternElem::ShiftLeft(_) | PatternElem::ShiftRight(_) => 2,
+            PatternElem::Mod(_) | PatternElem::Square | PatternElem::Cube | PatternElem::Pow(_) |
+            PatternElem::Sum(_) | PatternElem::And(_) | PatternElem::Or(_) | PatternElem::Xor(_) => 3,
+            PatternElem::Const(_) | PatternElem::SquareRoot | PatternElem::CubeRoot | PatternElem::Root(_) |
+            PatternElem::Custom(_) | PatternElem::Product(_) | PatternElem::Index(_) | PatternElem::Digit(..) |
+            PatternElem::RotateLeft(_) => 4,
+            PatternElem::Linear(_) => 5,
+            PatternElem::Meta(_) => META_COST,
+        }
+    }
+
+    /// Determines whether two operations differ only in their operands. Powers are all the same
+    /// type regardless of their exponent (i.e. `Square`, `Cube` and `Pow`), as are roots.
     pub fn same_operator_type(&self, other: &Self) -> bool {
         match (self, other) {
-            (&PatternElem::Custom(ref p1), &PatternElem::Custom(ref p2)) => p1 == p2,
-            (&PatternElem::Const(_), &PatternElem::Const(_)) |
-            (&PatternElem::Cube, &PatternElem::Cube) |
-            (&PatternElem::CubeRoot, &PatternElem::CubeRoot) |
-            (&PatternElem::Div(_), &PatternElem::Div(_)) |
-            (&PatternElem::Mod(_), &PatternElem::Mod(_)) |
-            (&PatternElem::Mult(_), &PatternElem::Mult(_)) |
-            (&PatternElem::Plus(_), &PatternElem::Plus(_)) |
-            (&PatternElem::Square, &PatternElem::SquareRoot) |
-            (&PatternElem::SquareRoot, &PatternElem::SquareRoot) => true,
-            _ => false
+            (PatternElem::Custom(p1), PatternElem::Custom(p2)) => p1 == p2,
+            (PatternElem::Digit(op1, radix1), PatternElem::Digit(op2, radix2)) => {
+                mem::discriminant(op1) == mem::discriminant(op2) && radix1 == radix2
+            }
+            (PatternElem::Const(_), PatternElem::Const(_)) |
+            (PatternElem::Div(_), PatternElem::Div(_)) |
+            (PatternElem::Mod(_), PatternElem::Mod(_)) |
+            (PatternElem::Mult(_), PatternElem::Mult(_)) |
+            (PatternElem::Plus(_), PatternElem::Plus(_)) |
+            (PatternElem::Product(_), PatternElem::Product(_)) |
+            (PatternElem::Sum(_), PatternElem::Sum(_)) |
+            (PatternElem::Linear(_), PatternElem::Linear(_)) |
+            (PatternElem::Index(_), PatternElem::Index(_)) |
+            (PatternElem::And(_), PatternElem::And(_)) |
+            (PatternElem::Or(_), PatternElem::Or(_)) |
+            (PatternElem::Xor(_), PatternElem::Xor(_)) |
+            (PatternElem::ShiftLeft(_), PatternElem::ShiftLeft(_)) |
+            (PatternElem::ShiftRight(_), PatternElem::ShiftRight(_)) |
+            (PatternElem::RotateLeft(_), PatternElem::RotateLeft(_)) => true,
+            _ => self.is_power() && other.is_power() || self.is_root() && other.is_root(),
         }
     }
+
+    fn is_power(&self) -> bool {
+        matches!(*self, PatternElem::Square | PatternElem::Cube | PatternElem::Pow(_))
+    }
+
+    fn is_root(&self) -> bool {
+        matches!(*self, PatternElem::SquareRoot | PatternElem::CubeRoot | PatternElem::Root(_))
+    }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
-pub struct CustomPatternElem {
-    check: fn(i32, i32) -> bool,
+/// An operation that can be looked for alongside the built-in ones (see `CustomPatternElem`).
+/// Unlike a plain function, an implementation can carry parameters, e.g. the exponent of a power.
+pub trait CustomOp<T = i32>: Send + Sync {
+    /// Determines whether the operation leads from `x` to `y`.
+    fn check(&self, x: T, y: T) -> bool;
+
+    /// Computes the operation, which allows patterns containing it to be extrapolated. By default,
+    /// it can't be computed.
+    fn apply(&self, _x: T) -> Option<T> {
+        None
+    }
+
+    /// The name that the operation is displayed with, which identifies it.
+    fn name(&self) -> String;
+}
+
+/// The `CustomOp` made from the functions passed to `CustomPatternElem::new` and `with_apply`.
+struct FnOp<T> {
+    check: Box<dyn Fn(T, T) -> bool + Send + Sync>,
+    apply: Option<Box<dyn Fn(T) -> Option<T> + Send + Sync>>,
+    name: String,
+}
+
+impl<T> CustomOp<T> for FnOp<T> {
+    fn check(&self, x: T, y: T) -> bool {
+        (self.check)(x, y)
+    }
+
+    fn apply(&self, x: T) -> Option<T> {
+        self.apply.as_ref().and_then(|apply| apply(x))
+    }
+
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+/// A custom operation that can be used in patterns.
+///
+/// Custom operations are identified by their names: two of them with the same name are equal
+/// (and are hashed and ordered by their names), whatever they compute.
+#[derive(Clone)]
+pub struct CustomPatternElem<T = i32> {
+    op: Arc<dyn CustomOp<T>>,
     repr: String,
 }
 
-impl CustomPatternElem {
-    pub fn new(check: fn(i32, i32) -> bool, repr: &str) -> Self {
-        CustomPatternElem { check: check, repr: String::from(repr) }
+impl<T: 'static> CustomPatternElem<T> {
+    /// Creates an operation that leads from `x` to `y` whenever `check(x, y)` holds. `check` may
+    /// be a closure, e.g. one that captures an exponent.
+    pub fn new<F>(check: F, repr: &str) -> Self where F: Fn(T, T) -> bool + Send + Sync + 'static {
+        Self::from_op(FnOp { check: Box::new(check), apply: None, name: String::from(repr) })
+    }
+
+    /// Same as `new`, but also takes a function that computes the operation, which allows patterns
+    /// containing the element to be extrapolated.
+    pub fn with_apply<F, A>(check: F, apply: A, repr: &str) -> Self
+        where F: Fn(T, T) -> bool + Send + Sync + 'static, A: Fn(T) -> Option<T> + Send + Sync + 'static {
+        Self::from_op(FnOp { check: Box::new(check), apply: Some(Box::new(apply)), name: String::from(repr) })
+    }
+
+    /// Creates an element from an implementation of `CustomOp`, named after `op.name()`.
+    pub fn from_op<O: CustomOp<T> + 'static>(op: O) -> Self {
+        let repr = op.name();
+        CustomPatternElem { op: Arc::new(op), repr }
+    }
+}
+
+impl<T> CustomPatternElem<T> {
+    /// Returns the name that the element is displayed with.
+    pub fn name(&self) -> &str {
+        &self.repr
     }
 
-    pub fn check(&self, x: i32, y: i32) -> bool {
-        let check = self.check;
-        check(x, y)
+    pub fn check(&self, x: T, y: T) -> bool {
+        self.op.check(x, y)
+    }
+
+    pub fn apply(&self, x: T) -> Option<T> {
+        self.op.apply(x)
     }
 }
 
-impl Display for PatternElem {
-    fn fmt(&self, mut fmt: &mut Formatter) -> Result<(), Error> {
+impl<T> Debug for CustomPatternElem<T> {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        fmt.debug_struct("CustomPatternElem").field("name", &self.repr).finish()
+    }
+}
+
+impl<T> PartialEq for CustomPatternElem<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.repr == other.repr
+    }
+}
+
+impl<T> Eq for CustomPatternElem<T> {}
+
+impl<T> Hash for CustomPatternElem<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.repr.hash(state);
+    }
+}
+
+impl<T> Ord for CustomPatternElem<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.repr.cmp(&other.repr)
+    }
+}
+
+impl<T> PartialOrd for CustomPatternElem<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Number> Display for PatternElem<T> {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
         match *self {
-            PatternElem::Const(i) => write!(fmt, "={}", i),
-            PatternElem::Plus(i) if i < 0 => write!(fmt, "-{}", i.abs()),
-            PatternElem::Plus(i) => write!(fmt, "+{}", i),
-            PatternElem::Mult(i) => write!(fmt, "*{}", i),
-            PatternElem::Div(i) => write!(fmt, "/{}", i),
-            PatternElem::Mod(i) => write!(fmt, "%{}", i),
+            PatternElem::Const(ref i) => write!(fmt, "={}", i),
+            // Negative numbers are already displayed with a sign.
+            PatternElem::Plus(ref i) if i.is_negative() => write!(fmt, "{}", i),
+            PatternElem::Plus(ref i) => write!(fmt, "+{}", i),
+            PatternElem::Mult(ref i) => write!(fmt, "*{}", i),
+            PatternElem::Div(ref i) => write!(fmt, "/{}", i),
+            PatternElem::Mod(ref i) => write!(fmt, "%{}", i),
             PatternElem::Square => write!(fmt, "^2"),
             PatternElem::Cube => write!(fmt, "^3"),
             PatternElem::SquareRoot => write!(fmt, "root 2"),
             PatternElem::CubeRoot => write!(fmt, "root 3"),
+            PatternElem::Pow(ref n) => write!(fmt, "^{}", n),
+            PatternElem::Root(ref n) => write!(fmt, "root {}", n),
+            PatternElem::And(ref m) => write!(fmt, "and {}", m),
+            PatternElem::Or(ref m) => write!(fmt, "or {}", m),
+            PatternElem::Xor(ref m) => write!(fmt, "xor {}", m),
+            PatternElem::ShiftLeft(ref n) => write!(fmt, "<<{}", n),
+            PatternElem::ShiftRight(ref n) => write!(fmt, ">>{}", n),
+            PatternElem::RotateLeft(ref n) => write!(fmt, "rotl {}", n),
+            PatternElem::Product(k) => write!(fmt, "product {}", k),
+            PatternElem::Sum(k) => write!(fmt, "sum {}", k),
+            PatternElem::Linear(ref linear) => write!(fmt, "{}", linear),
+            PatternElem::Index(ref formula) => write!(fmt, "{}", formula),
+            PatternElem::Digit(ref op, radix) => fmt_digit_op(fmt, op, radix),
             PatternElem::Custom(CustomPatternElem { ref repr, .. }) => write!(fmt, "{}", repr),
-            PatternElem::Meta(ref pat) => write!(fmt, "[{}...]", pat),
+            PatternElem::Meta(ref pat) => match pat.explicit_operand_pattern() {
+                Some(operands) => write!(fmt, "[{}... | {}]", pat, operands),
+                None => write!(fmt, "[{}...]", pat),
+            },
         }
     }
 }
 
-/// A sequence of operations defining a pattern.
-#[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Pattern(Vec<PatternElem>);
+/// A sequence of operations defining a pattern. Treating it as the operations of a meta-pattern,
+/// it may also hold the pattern describing their operands, if it isn't the one that would be
+/// found for them by default (see `operand_pattern`).
+#[derive(Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Pattern<T = i32>(Vec<PatternElem<T>>, Option<Box<Pattern<T>>>);
+
+impl<T: Debug> Debug for Pattern<T> {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        let mut tuple = fmt.debug_tuple("Pattern");
+        tuple.field(&self.0);
+
+        if let Some(ref operands) = self.1 {
+            tuple.field(operands);
+        }
+
+        tuple.finish()
+    }
+}
 
-impl IntoIterator for Pattern {
-    type Item = PatternElem;
-    type IntoIter = ::std::vec::IntoIter<PatternElem>;
+impl<T> IntoIterator for Pattern<T> {
+    type Item = PatternElem<T>;
+    type IntoIter = ::std::vec::IntoIter<PatternElem<T>>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.0.into_iter()
     }
 }
 
-impl FromIterator<PatternElem> for Pattern {
-    fn from_iter<I: IntoIterator<Item=PatternElem>>(iterator: I) -> Self {
-        Pattern(iterator.into_iter().collect())
+impl<T> FromIterator<PatternElem<T>> for Pattern<T> {
+    fn from_iter<I: IntoIterator<Item=PatternElem<T>>>(iterator: I) -> Self {
+        Pattern(iterator.into_iter().collect(), None)
     }
 }
 
@@ -107,10 +504,29 @@ macro_rules! pat {
     ($($elem:expr),*) => (Pattern::new(vec![$($elem),*]))
 }
 
-impl Pattern {
+impl<T: Number> Pattern<T> {
     /// Constructs a new pattern given a vector of operations.
-    pub fn new(elems: Vec<PatternElem>) -> Self {
-        Pattern(elems)
+    pub fn new(elems: Vec<PatternElem<T>>) -> Self {
+        Pattern(elems, None)
+    }
+
+    /// Constructs the operations of a meta-pattern along with the pattern describing their
+    /// operands. It's only kept if it isn't the one that would be found for them by default, so
+    /// that patterns that behave the same compare equal.
+    pub(crate) fn with_operand_pattern(elems: Vec<PatternElem<T>>, operands: Pattern<T>) -> Self {
+        let mut pat = Pattern::new(elems);
+
+        if pat.default_operand_pattern().as_ref() != Some(&operands) {
+            pat.1 = Some(Box::new(operands));
+        }
+
+        pat
+    }
+
+    /// Treating the pattern as the operations of a meta-pattern, returns the pattern describing
+    /// their operands if it was given explicitly (see `with_operand_pattern`).
+    pub(crate) fn explicit_operand_pattern(&self) -> Option<&Pattern<T>> {
+        self.1.as_deref()
     }
 
     /// Constructs a new empty pattern.
@@ -139,7 +555,7 @@ impl Pattern {
     /// assert_eq!(pats, vec![pat![Plus(3), Mult(2), Div(2)], pat![Plus(3), Mult(2), Div(3)]]);
     /// # }
     /// ```
-    pub fn extend_each<T>(&self, iter: T) -> Vec<Self> where T: Iterator<Item=PatternElem> {
+    pub fn extend_each<I>(&self, iter: I) -> Vec<Self> where I: Iterator<Item=PatternElem<T>> {
         iter.map(|elem| {
             let mut v = self.0.clone();
             v.push(elem);
@@ -147,33 +563,266 @@ impl Pattern {
         }).collect()
     }
 
-    pub fn iter(&self) -> Iter<PatternElem> {
+    pub fn iter(&self) -> Iter<'_, PatternElem<T>> {
         self.0.iter()
     }
 
+    /// Determines whether the operation types repeat, with a whole period of them showing up at
+    /// least twice, as they have to for the operations of a meta-pattern.
     pub fn has_repeating_types(&self) -> bool {
         is_repeating_with_predicate(&self.0, |x, y| x.same_operator_type(y))
     }
+
+    /// Returns the operation that the pattern applies at the `n`th transition of a sequence (i.e.
+    /// the one from the `n`th term to the `n + 1`th term). Meta-patterns are resolved to the
+    /// operation they describe at that point, extrapolating their operands if necessary.
+    pub fn elem_at(&self, n: usize) -> Option<PatternElem<T>> {
+        if self.is_empty() {
+            return None;
+        }
+
+        match self.0[n % self.len()] {
+            PatternElem::Meta(ref meta) => meta.extrapolate(n / self.len()),
+            ref elem => Some(elem.clone()),
+        }
+    }
+
+    /// Computes the `n` terms that follow `last`, given that `last` is the term reached after
+    /// `pos` transitions. Stops early if a term can't be computed.
+    /// Recurrences can't be computed from `last` alone, so prediction stops at them (see
+    /// `predict_next`).
+    pub fn predict(&self, last: T, pos: usize, n: usize) -> Vec<T> {
+        self.predict_after(vec![last], pos, n)
+    }
+
+    /// Same as `predict`, but given every term up to the one reached after `pos` transitions.
+    fn predict_after(&self, mut prev: Vec<T>, pos: usize, n: usize) -> Vec<T> {
+        let start = prev.len();
+        let index_terms = IndexTerms::new(self, pos + n + 1);
+
+        for i in pos..pos + n {
+            // `prev` may not start from the first term, so index formulas are given the index of
+            // the term after the `i`th transition rather than counting `prev`.
+            let next = self.elem_at(i).and_then(|elem| match elem {
+                PatternElem::Index(ref formula) => index_terms.get(formula, i + 1),
+                ref elem => elem.apply_after(&prev),
+            });
+
+            match next {
+                Some(y) => prev.push(y),
+                None => break,
+            }
+        }
+
+        prev.split_off(start)
+    }
+
+    /// Computes the `n` terms that follow `seq`, assuming that the pattern describes `seq` from
+    /// its first term.
+    ///
+    /// ```
+    /// # #[macro_use] extern crate sea_canal;
+    /// # use sea_canal::Pattern;
+    /// # use sea_canal::PatternElem::{Plus, Mult};
+    /// # fn main() {
+    /// let pat = pat![Plus(3), Mult(2)];
+    /// assert_eq!(vec![50, 53, 106], pat.predict_next(&[1, 4, 8, 11, 22, 25], 3));
+    /// # }
+    /// ```
+    pub fn predict_next(&self, seq: &[T], n: usize) -> Vec<T> {
+        if seq.is_empty() {
+            return Vec::new();
+        }
+
+        self.predict_after(seq.to_vec(), seq.len() - 1, n)
+    }
+
+    /// Checks each term of `seq` against the operation the pattern expects to lead to it from the
+    /// previous term, assuming that the pattern describes `seq` from its first term, and returns
+    /// the terms that don't match. Each transition is checked on its own, so a single wrong term
+    /// is usually reported along with the term after it.
+    ///
+    /// ```
+    /// # #[macro_use] extern crate sea_canal;
+    /// # use sea_canal::{Analyzer, Pattern};
+    /// # use sea_canal::PatternElem::Plus;
+    /// # fn main() {
+    /// let seq = &[1, 4, 3, 6, 5, 8, 10, 9];
+    /// let pat = Analyzer::from_slice(&seq[..5]).find_best_pattern(2).unwrap();
+    /// let anomalies = pat.anomalies(seq);
+    ///
+    /// assert_eq!(pat![Plus(3), Plus(-1)], pat);
+    /// assert_eq!(vec![6, 7], anomalies.iter().map(|anomaly| anomaly.index).collect::<Vec<_>>());
+    /// assert_eq!("term 6: expected 7 (-1), found 10", format!("{}", anomalies[0]));
+    /// # }
+    /// ```
+    pub fn anomalies(&self, seq: &[T]) -> Vec<Anomaly<T>> {
+        let index_terms = IndexTerms::new(self, seq.len());
+        (0..seq.len()).filter_map(|i| self.anomaly_at(seq, i, &index_terms)).collect()
+    }
+
+    /// Same as `anomalies`, but stops at the first term that doesn't match.
+    pub fn first_anomaly(&self, seq: &[T]) -> Option<Anomaly<T>> {
+        let index_terms = IndexTerms::new(self, seq.len());
+        (0..seq.len()).filter_map(|i| self.anomaly_at(seq, i, &index_terms)).next()
+    }
+
+    /// Determines whether the pattern describes `seq` from its first term, i.e. whether each term
+    /// is reached from the previous one by the operation the pattern expects there (see
+    /// `elem_at`). No transition leads to the first term, but if the pattern would reach it with
+    /// an index formula (i.e. its last operation, were it to wrap around), it's checked too.
+    ///
+    /// ```
+    /// # #[macro_use] extern crate sea_canal;
+    /// # use sea_canal::Pattern;
+    /// # use sea_canal::PatternElem::Plus;
+    /// # fn main() {
+    /// let pat = pat![Plus(3), Plus(-1)];
+    ///
+    /// assert!(pat.matches(&[1, 4, 3, 6, 5]));
+    /// assert!(!pat.matches(&[1, 4, 3, 7, 6]));
+    /// assert_eq!(Some(3), pat.first_mismatch(&[1, 4, 3, 7, 6]));
+    /// # }
+    /// ```
+    pub fn matches(&self, seq: &[T]) -> bool {
+        self.first_mismatch(seq).is_none()
+    }
+
+    /// Returns the index of the first term of `seq` that the pattern doesn't lead to, if any (see
+    /// `first_anomaly` for what was expected there instead).
+    pub fn first_mismatch(&self, seq: &[T]) -> Option<usize> {
+        self.first_anomaly(seq).map(|anomaly| anomaly.index)
+    }
+
+    fn anomaly_at(&self, seq: &[T], i: usize, index_terms: &IndexTerms<T>) -> Option<Anomaly<T>> {
+        let elem = match i.checked_sub(1) {
+            Some(n) => self.elem_at(n),
+            None => Some(self.0.last().filter(|elem| matches!(**elem, PatternElem::Index(_)))?.clone()),
+        };
+
+        let expected = match elem {
+            Some(PatternElem::Index(ref formula)) => {
+                let term = index_terms.get(formula, i);
+
+                if term.as_ref() == Some(&seq[i]) {
+                    return None;
+                }
+
+                term
+            }
+            ref elem => {
+                if elem.as_ref().is_some_and(|elem| elem.describes_after(&seq[..i], &seq[i])) {
+                    return None;
+                }
+
+                elem.as_ref().and_then(|elem| elem.apply_after(&seq[..i]))
+            }
+        };
+
+        Some(Anomaly { index: i, expected, elem, actual: seq[i].clone() })
+    }
+
+    /// Returns the total cost of the pattern's operations (see `PatternElem::cost`). Lower costs
+    /// indicate simpler patterns.
+    pub fn cost(&self) -> usize {
+        self.iter().map(PatternElem::cost).sum()
+    }
+
+    /// Treating the pattern as the operations of a meta-pattern, returns its cost: one period of
+    /// its operation types, plus the first operand and the pattern that yields the rest of them.
+    /// If the operands don't follow a pattern, each operation is paid for in full instead.
+    fn meta_cost(&self) -> usize {
+        let period = period_with_predicate(&self.0, |x, y| x.same_operator_type(y)).unwrap_or_else(|| self.len());
+        let types: usize = self.iter().take(period).map(PatternElem::base_cost).sum();
+
+        let described = self.operand_pattern().map(|pat| {
+            let first = self.iter().next().and_then(PatternElem::get_operand).as_ref().map_or(0, operand_cost);
+            first + pat.cost()
+        });
+
+        META_COST + described.map_or_else(|| self.cost(), |operands| types + operands)
+    }
+
+    /// Treating the pattern as the operations of a meta-pattern, returns the `k`th operation
+    /// described by it.
+    fn extrapolate(&self, k: usize) -> Option<PatternElem<T>> {
+        if k < self.len() {
+            return Some(self.0[k].clone());
+        }
+
+        let period = period_with_predicate(&self.0, |x, y| x.same_operator_type(y))?;
+        let operands = self.iter().map(PatternElem::get_operand).collect::<Option<Vec<_>>>()?;
+        let operand = self.operand_pattern()?
+                          .predict_next(&operands, k + 1 - self.len())
+                          .into_iter()
+                          .nth(k - self.len())?;
+
+        self.0[k % period].with_operand(operand)
+    }
+
+    /// Treating the pattern as the operations of a meta-pattern, returns the pattern describing
+    /// their operands: the one given along with the operations, if any, and otherwise the one
+    /// found for them by default (see `default_operand_pattern`).
+    fn operand_pattern(&self) -> Option<Cow<'_, Pattern<T>>> {
+        match self.1 {
+            Some(ref operands) => Some(Cow::Borrowed(operands)),
+            None => self.default_operand_pattern().map(Cow::Owned),
+        }
+    }
+
+    /// Finds the pattern describing the operands of a meta-pattern with the default operations,
+    /// in meta-patterns up to `MAX_OPERAND_DEPTH` levels deep. It only depends on the operations,
+    /// so patterns that compare equal always find the same one.
+    fn default_operand_pattern(&self) -> Option<Pattern<T>> {
+        let operands = self.iter().map(PatternElem::get_operand).collect::<Option<Vec<_>>>()?;
+        MetaAnalyzer::operand_pattern(&operands, MAX_OPERAND_DEPTH, &AnalyzerBuilder::new())
+    }
 }
 
-impl Display for Pattern {
-    fn fmt(&self, mut fmt: &mut Formatter) -> Result<(), Error> {
+impl<T: Number> Display for Pattern<T> {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
         for (i, elem) in self.0.iter().enumerate() {
             if i != 0 {
-                try!(write!(fmt, ", "));
+                write!(fmt, ", ")?;
             }
 
-            try!(write!(fmt, "{}", elem));
+            write!(fmt, "{}", elem)?;
         }
 
         Ok(())
     }
 }
 
+/// The first terms of each index formula of a pattern, which are generated in a single pass rather
+/// than from scratch at each position that they're needed at.
+struct IndexTerms<T>(Vec<(IndexFormula<T>, Vec<T>)>);
+
+impl<T: Number> IndexTerms<T> {
+    /// Generates the first `len` terms of each index formula of `pat`.
+    fn new(pat: &Pattern<T>, len: usize) -> Self {
+        let mut terms: Vec<(IndexFormula<T>, Vec<T>)> = Vec::new();
+
+        for elem in pat.iter() {
+            if let PatternElem::Index(ref formula) = *elem {
+                if terms.iter().all(|(other, _)| other != formula) {
+                    terms.push((formula.clone(), formula.terms().take(len).collect()));
+                }
+            }
+        }
+
+        IndexTerms(terms)
+    }
+
+    /// Returns the `n`th term of `formula`, or `None` if `T` can't represent it.
+    fn get(&self, formula: &IndexFormula<T>, n: usize) -> Option<T> {
+        self.0.iter().find(|(other, _)| other == formula)?.1.get(n).cloned()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::PatternElem::*;
-    use super::Pattern;
+    use super::{Pattern, PatternElem};
 
     #[test]
     fn fmt_pat_elem_plus() {
@@ -213,30 +862,72 @@ mod tests {
 
     #[test]
     fn fmt_pat_elem_square() {
-        assert_eq!("^2", format!("{}", Square));
+        assert_eq!("^2", format!("{}", PatternElem::<i32>::Square));
     }
 
     #[test]
     fn fmt_pat_elem_cube() {
-        assert_eq!("^3", format!("{}", Cube));
+        assert_eq!("^3", format!("{}", PatternElem::<i32>::Cube));
     }
 
     #[test]
     fn fmt_pat_elem_square_root() {
-        assert_eq!("root 2", format!("{}", SquareRoot));
+        assert_eq!("root 2", format!("{}", PatternElem::<i32>::SquareRoot));
     }
 
     #[test]
     fn fmt_pat_elem_cube_root() {
-        assert_eq!("root 3", format!("{}", CubeRoot));
+        assert_eq!("root 3", format!("{}", PatternElem::<i32>::CubeRoot));
+    }
+
+    #[test]
+    fn apply_pat_elem() {
+        assert_eq!(Some(7), Plus(3).apply(&4));
+        assert_eq!(Some(2), Div(3).apply(&6));
+        assert_eq!(None, Div(4).apply(&6));
+        assert_eq!(Some(2), Mod(4).apply(&6));
+        assert_eq!(Some(-27), Cube.apply(&-3));
+        assert_eq!(Some(-3), CubeRoot.apply(&-27));
+        assert_eq!(Some(12), SquareRoot.apply(&144));
+        assert_eq!(None, SquareRoot.apply(&143));
+        assert_eq!(None, SquareRoot.apply(&-4));
+        assert_eq!(None, Square.apply(&(1 << 16)));
+        assert_eq!(Some(1 << 32), Square.apply(&(1i64 << 16)));
     }
 
     #[test]
     fn fmt_pat() {
-        assert_eq!("", format!("{}", Pattern::empty()));
+        assert_eq!("", format!("{}", Pattern::<i32>::empty()));
         assert_eq!("+4", format!("{}", pat![Plus(4)]));
         assert_eq!("+4, %-6", format!("{}", pat![Plus(4), Mod(-6)]));
         assert_eq!("+4, %-6, -12, *42, /3, =9", format!("{}", pat![Plus(4), Mod(-6), Plus(-12), Mult(42), Div(3), Const(9)]));
-        assert_eq!("^2, root 2, ^3, root 3", format!("{}", pat![Square, SquareRoot, Cube, CubeRoot]));
+        assert_eq!("^2, root 2, ^3, root 3", format!("{}", pat![PatternElem::<i32>::Square, SquareRoot, Cube, CubeRoot]));
+    }
+
+    #[test]
+    fn cost() {
+        assert_eq!(Plus(2).cost(), Plus(-2).cost());
+        assert!(Plus(2).cost() < Mult(2).cost());
+        assert!(Plus(200).cost() > Mult(2).cost());
+        assert!(pat![Plus(2), Plus(-2)].cost() < pat![PatternElem::<i32>::Square, SquareRoot].cost());
+        assert!(pat![Meta(pat![Plus(1), Plus(2), Plus(3), Plus(4)])].cost() < pat![Meta(pat![Plus(1), Plus(3), Plus(2), Plus(4)])].cost());
+    }
+
+    #[test]
+    fn powers_and_roots() {
+        assert_eq!(Some(Pow(4)), PatternElem::pow(4));
+        assert_eq!(Some(Square), PatternElem::pow(2));
+        assert_eq!(Some(CubeRoot), PatternElem::root(3));
+        assert_eq!(None, PatternElem::pow(1));
+        assert_eq!(None, PatternElem::root(-4));
+        assert_eq!(Some(2), PatternElem::<i32>::SquareRoot.get_operand());
+        assert_eq!(Some(Cube), Pow(5).with_operand(3));
+        assert_eq!(Some(Root(6)), SquareRoot.with_operand(6));
+        assert!(Square.same_operator_type(&Pow(7)));
+        assert!(!PatternElem::<i32>::Square.same_operator_type(&SquareRoot));
+        assert_eq!(Some(81), Pow(4).apply(&3));
+        assert_eq!(Some(2), Root(5).apply(&32));
+        assert_eq!(None, Root(5).apply(&33));
+        assert_eq!("^4, root 5", format!("{}", pat![Pow(4), Root(5)]));
     }
 }
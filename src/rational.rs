@@ -0,0 +1,339 @@
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter, Error};
+use std::str::FromStr;
+
+use error::ParseNumberError;
+use number::Number;
+
+/// An exact fraction of two integers, which allows sequences with fractional steps (e.g. halving)
+/// to be analyzed.
+///
+/// Fractions are always kept in lowest terms with a positive denominator, so two fractions are
+/// equal exactly when they represent the same number.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Rational<T = i64> {
+    numer: T,
+    denom: T,
+}
+
+impl<T: Number> Rational<T> {
+    /// Constructs a new fraction, returning `None` if the denominator is zero (or if the fraction
+    /// can't be represented in lowest terms).
+    pub fn new(numer: T, denom: T) -> Option<Self> {
+        if denom == T::zero() {
+            return None;
+        }
+
+        let divisor = gcd(&numer, &denom)?;
+        let (numer, denom) = (numer.exact_div(&divisor)?, denom.exact_div(&divisor)?);
+
+        if denom.is_negative() {
+            Some(Rational { numer: T::zero().checked_sub(&numer)?, denom: T::zero().checked_sub(&denom)? })
+        } else {
+            Some(Rational { numer, denom })
+        }
+    }
+
+    pub fn from_integer(n: T) -> Self {
+        Rational { numer: n, denom: T::one() }
+    }
+
+    pub fn numer(&self) -> &T {
+        &self.numer
+    }
+
+    pub fn denom(&self) -> &T {
+        &self.denom
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.denom == T::one()
+    }
+}
+
+impl<T: Number> From<T> for Rational<T> {
+    fn from(n: T) -> Self {
+        Rational::from_integer(n)
+    }
+}
+
+/// Finds the (non-negative) greatest common divisor of two integers.
+fn gcd<T: Number>(a: &T, b: &T) -> Option<T> {
+    let (mut a, mut b) = (a.clone(), b.clone());
+
+    while b != T::zero() {
+        let rem = a.checked_rem(&b)?;
+        a = b;
+        b = rem;
+    }
+
+    a.checked_abs()
+}
+
+/// Divides `a` by the positive integer `b`, rounding down, and returns the quotient and the
+/// (non-negative) remainder.
+fn floor_div_rem<T: Number>(a: &T, b: &T) -> (T, T) {
+    // Truncated division moves towards zero and the remainder is smaller in magnitude than `b`, so
+    // none of these operations can overflow.
+    let rem = a.checked_rem(b).expect("remainder by a positive integer");
+    let quot = a.checked_sub(&rem).and_then(|n| n.exact_div(b)).expect("exact division");
+
+    if rem.is_negative() {
+        (quot.checked_sub(&T::one()).expect("quotient above minimum"), rem.checked_add(b).expect("remainder below zero"))
+    } else {
+        (quot, rem)
+    }
+}
+
+impl<T: Number> Ord for Rational<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Cross-multiplying could overflow, so this compares the continued fraction expansions of
+        // the two numbers instead.
+        let (mut a, mut b) = (self.numer.clone(), self.denom.clone());
+        let (mut c, mut d) = (other.numer.clone(), other.denom.clone());
+        let mut reversed = false;
+
+        loop {
+            let (q1, r1) = floor_div_rem(&a, &b);
+            let (q2, r2) = floor_div_rem(&c, &d);
+
+            let ordering = match (q1.cmp(&q2), r1 == T::zero(), r2 == T::zero()) {
+                (Ordering::Equal, true, true) => Ordering::Equal,
+                (Ordering::Equal, true, false) => Ordering::Less,
+                (Ordering::Equal, false, true) => Ordering::Greater,
+                (Ordering::Equal, false, false) => {
+                    // `r1 / b < r2 / d` exactly when `b / r1 > d / r2`.
+                    a = b;
+                    b = r1;
+                    c = d;
+                    d = r2;
+                    reversed = !reversed;
+                    continue;
+                }
+                (ordering, _, _) => ordering,
+            };
+
+            return if reversed { ordering.reverse() } else { ordering };
+        }
+    }
+}
+
+impl<T: Number> PartialOrd for Rational<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Number> Display for Rational<T> {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        if self.is_integer() {
+            write!(fmt, "{}", self.numer)
+        } else {
+            write!(fmt, "{}/{}", self.numer, self.denom)
+        }
+    }
+}
+
+/// Parses a fraction written the way it's displayed, as an integer or as `numer/denom`.
+impl<T: Number + FromStr> FromStr for Rational<T> {
+    type Err = ParseNumberError;
+
+    fn from_str(s: &str) -> Result<Self, ParseNumberError> {
+        let (numer, denom) = s.split_once('/').unwrap_or((s, "1"));
+        let numer = numer.parse().map_err(|_| ParseNumberError)?;
+        let denom = denom.parse().map_err(|_| ParseNumberError)?;
+
+        Rational::new(numer, denom).ok_or(ParseNumberError)
+    }
+}
+
+impl<T: Number> Number for Rational<T> {
+    fn zero() -> Self {
+        Rational::from_integer(T::zero())
+    }
+
+    fn one() -> Self {
+        Rational::from_integer(T::one())
+    }
+
+    fn is_negative(&self) -> bool {
+        self.numer.is_negative()
+    }
+
+    fn checked_add(&self, other: &Self) -> Option<Self> {
+        self.combine_numers(other, T::checked_add)
+    }
+
+    fn checked_sub(&self, other: &Self) -> Option<Self> {
+        self.combine_numers(other, T::checked_sub)
+    }
+
+    fn checked_mul(&self, other: &Self) -> Option<Self> {
+        // Reducing before multiplying keeps the intermediate values as small as possible.
+        let (g1, g2) = (gcd(&self.numer, &other.denom)?, gcd(&other.numer, &self.denom)?);
+
+        let numer = self.numer.exact_div(&g1)?.checked_mul(&other.numer.exact_div(&g2)?)?;
+        let denom = self.denom.exact_div(&g2)?.checked_mul(&other.denom.exact_div(&g1)?)?;
+
+        Rational::new(numer, denom)
+    }
+
+    fn exact_div(&self, other: &Self) -> Option<Self> {
+        let reciprocal = Rational::new(other.denom.clone(), other.numer.clone())?;
+        self.checked_mul(&reciprocal)
+    }
+
+    /// Remainders are only defined between integers.
+    fn checked_rem(&self, other: &Self) -> Option<Self> {
+        if self.is_integer() && other.is_integer() {
+            self.numer.checked_rem(&other.numer).map(Rational::from_integer)
+        } else {
+            None
+        }
+    }
+
+    fn checked_abs(&self) -> Option<Self> {
+        Some(Rational { numer: self.numer.checked_abs()?, denom: self.denom.clone() })
+    }
+
+    fn exact_root(&self, n: u32) -> Option<Self> {
+        Rational::new(self.numer.exact_root(n)?, self.denom.exact_root(n)?)
+    }
+
+    fn from_u32(n: u32) -> Option<Self> {
+        T::from_u32(n).map(Rational::from_integer)
+    }
+
+    fn to_u32(&self) -> Option<u32> {
+        if self.is_integer() {
+            self.numer.to_u32()
+        } else {
+            None
+        }
+    }
+
+    /// Bitwise operations are only defined on integers, like remainders.
+    fn checked_xor(&self, other: &Self) -> Option<Self> {
+        self.integer_op(|n| n.checked_xor(other.integer()?))
+    }
+
+    fn checked_and(&self, other: &Self) -> Option<Self> {
+        self.integer_op(|n| n.checked_and(other.integer()?))
+    }
+
+    fn checked_or(&self, other: &Self) -> Option<Self> {
+        self.integer_op(|n| n.checked_or(other.integer()?))
+    }
+
+    fn checked_not(&self) -> Option<Self> {
+        self.integer_op(T::checked_not)
+    }
+
+    fn exact_shl(&self, n: u32) -> Option<Self> {
+        self.integer_op(|numer| numer.exact_shl(n))
+    }
+
+    fn exact_shr(&self, n: u32) -> Option<Self> {
+        self.integer_op(|numer| numer.exact_shr(n))
+    }
+
+    fn rotate_bits_left(&self, n: u32) -> Option<Self> {
+        self.integer_op(|numer| numer.rotate_bits_left(n))
+    }
+
+    fn bit_width() -> Option<u32> {
+        T::bit_width()
+    }
+}
+
+impl<T: Number> Rational<T> {
+    /// Adds or subtracts two fractions by bringing them to their least common denominator and
+    /// combining the numerators with `op`, so that neither has to be negated (which unsigned types
+    /// can't do).
+    fn combine_numers<F: FnOnce(&T, &T) -> Option<T>>(&self, other: &Self, op: F) -> Option<Self> {
+        let divisor = gcd(&self.denom, &other.denom)?;
+        let (lhs, rhs) = (other.denom.exact_div(&divisor)?, self.denom.exact_div(&divisor)?);
+        let numer = op(&self.numer.checked_mul(&lhs)?, &other.numer.checked_mul(&rhs)?)?;
+
+        Rational::new(numer, self.denom.checked_mul(&lhs)?)
+    }
+
+    fn integer(&self) -> Option<&T> {
+        if self.is_integer() {
+            Some(&self.numer)
+        } else {
+            None
+        }
+    }
+
+    /// Applies an operation that's only defined on integers.
+    fn integer_op<F: FnOnce(&T) -> Option<T>>(&self, op: F) -> Option<Self> {
+        op(self.integer()?).map(Rational::from_integer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use error::ParseNumberError;
+    use number::Number;
+    use super::Rational;
+
+    fn ratio(numer: i64, denom: i64) -> Rational {
+        Rational::new(numer, denom).unwrap()
+    }
+
+    #[test]
+    fn new_reduces() {
+        assert_eq!(ratio(1, 2), ratio(3, 6));
+        assert_eq!(ratio(-1, 2), ratio(3, -6));
+        assert_eq!(ratio(0, 1), ratio(0, -6));
+        assert_eq!(None, Rational::new(1, 0));
+    }
+
+    #[test]
+    fn cmp() {
+        assert!(ratio(1, 3) < ratio(1, 2));
+        assert!(ratio(-1, 2) < ratio(-1, 3));
+        assert!(ratio(7, 3) > ratio(2, 1));
+        assert!(ratio(i64::MAX, i64::MAX - 1) < ratio(i64::MAX - 1, i64::MAX - 2));
+        assert!(ratio(i64::MIN + 1, 3) < ratio(i64::MIN + 2, 3));
+        assert_eq!(ratio(5, 7).cmp(&ratio(10, 14)), ::std::cmp::Ordering::Equal);
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(Some(ratio(5, 6)), ratio(1, 2).checked_add(&ratio(1, 3)));
+        assert_eq!(Some(ratio(1, 6)), ratio(1, 2).checked_sub(&ratio(1, 3)));
+        assert_eq!(Some(ratio(1, 6)), ratio(1, 2).checked_mul(&ratio(1, 3)));
+        assert_eq!(Some(ratio(3, 2)), ratio(1, 2).exact_div(&ratio(1, 3)));
+        assert_eq!(None, ratio(1, 2).exact_div(&ratio(0, 1)));
+        assert_eq!(Some(ratio(2, 3)), ratio(4, 9).exact_root(2));
+        assert_eq!(None, ratio(1, 2).exact_root(2));
+        assert_eq!(Some(ratio(1, 1)), ratio(7, 1).checked_rem(&ratio(3, 1)));
+        assert_eq!(None, ratio(7, 2).checked_rem(&ratio(3, 1)));
+    }
+
+    #[test]
+    fn unsigned_arithmetic() {
+        let (a, b) = (Rational::new(3u32, 2).unwrap(), Rational::new(1u32, 2).unwrap());
+
+        assert_eq!(Some(Rational::from_integer(1)), a.checked_sub(&b));
+        assert_eq!(Some(Rational::from_integer(2)), a.checked_add(&b));
+        assert_eq!(None, b.checked_sub(&a));
+    }
+
+    #[test]
+    fn fmt() {
+        assert_eq!("-1/2", format!("{}", ratio(1, -2)));
+        assert_eq!("3", format!("{}", ratio(6, 2)));
+    }
+
+    #[test]
+    fn from_str() {
+        assert_eq!(Ok(ratio(-1, 2)), "-1/2".parse());
+        assert_eq!(Ok(ratio(3, 1)), "3".parse());
+        assert_eq!(Ok(ratio(1, 2)), "2/4".parse());
+        assert_eq!(Err(ParseNumberError), "1/0".parse::<Rational>());
+        assert_eq!(Err(ParseNumberError), "1/x".parse::<Rational>());
+    }
+}
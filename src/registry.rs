@@ -0,0 +1,44 @@
+use std::collections::HashMap;
+use std::iter::FromIterator;
+
+use pattern::CustomPatternElem;
+
+/// Custom operations that can be looked up by the name they're displayed with, so that patterns
+/// containing them can be read back in (see `Pattern::parse_with`).
+#[derive(Clone, Debug)]
+pub struct CustomRegistry<T = i32> {
+    elems: HashMap<String, CustomPatternElem<T>>,
+}
+
+impl<T> CustomRegistry<T> {
+    pub fn new() -> Self {
+        CustomRegistry { elems: HashMap::new() }
+    }
+
+    /// Adds a custom operation, replacing any other one with the same name.
+    pub fn register(&mut self, elem: CustomPatternElem<T>) {
+        self.elems.insert(elem.name().to_string(), elem);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&CustomPatternElem<T>> {
+        self.elems.get(name)
+    }
+}
+
+impl<T> Default for CustomRegistry<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> FromIterator<CustomPatternElem<T>> for CustomRegistry<T> {
+    fn from_iter<I: IntoIterator<Item=CustomPatternElem<T>>>(iterator: I) -> Self {
+        let mut registry = Self::new();
+
+        for elem in iterator {
+            registry.register(elem);
+        }
+
+        registry
+    }
+}
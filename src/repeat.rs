@@ -1,31 +1,19 @@
-// Ugh, this mix of imperative and functional programming feels really wrong
-pub fn is_repeating_with_predicate<T, P>(slice: &[T], is_match: P) -> bool where P: Fn(&T, &T) -> bool {
-    for i in 2..slice.len() {
-        let mut chunks = slice.chunks(i);
-
-        let first = match chunks.next() {
-            Some(chunk) => chunk,
-            None => continue,
-        };
-
-        // println!("first: {:#?}", first);
-
-        let mut matches = true;
+use std::ops::Range;
 
-        for chunk in chunks {
-            if !chunk.iter().zip(first.iter()).into_iter().all(|(x, y)| is_match(x, y)) {
-                matches = false;
-                // println!("chunk: {:#?}", chunk);
-                break;
-            }
-        }
+/// The lengths of the chunks that could repeat throughout a slice of `len` items. Each chunk has to
+/// fit at least twice in full, so that e.g. `a b c a` doesn't count as repeating.
+pub fn periods(len: usize) -> Range<usize> {
+    1..len / 2 + 1
+}
 
-        if matches {
-            return true;
-        }
-    }
+/// Finds the smallest length of a chunk that repeats throughout `slice` (see `periods`), where the
+/// last repetition may be partial.
+pub fn period_with_predicate<T, P>(slice: &[T], is_match: P) -> Option<usize> where P: Fn(&T, &T) -> bool {
+    periods(slice.len()).find(|&p| slice.iter().enumerate().skip(p).all(|(i, x)| is_match(x, &slice[i % p])))
+}
 
-    false
+pub fn is_repeating_with_predicate<T, P>(slice: &[T], is_match: P) -> bool where P: Fn(&T, &T) -> bool {
+    period_with_predicate(slice, is_match).is_some()
 }
 
 #[allow(dead_code)]
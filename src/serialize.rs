@@ -0,0 +1,207 @@
+//! Serialization of patterns, enabled by the `serde` feature.
+//!
+//! Each operation is serialized as an enum variant named after it (e.g. `{"Plus": 3}` or
+//! `"Square"` in JSON), and a pattern as a sequence of operations. Custom operations are
+//! serialized by name, since their functions can't be; `Deserialize` rejects them, so patterns
+//! containing them have to be deserialized with `CustomRegistry::deserialize_pattern`.
+
+use serde::de::{Deserialize, Deserializer, Error};
+use serde::ser::{Serialize, SerializeTupleVariant, Serializer};
+
+use digit::DigitOp;
+use index::{IndexFormula, MAX_START};
+use linear::LinearRecurrence;
+use number::Number;
+use pattern::{Pattern, PatternElem};
+use registry::CustomRegistry;
+
+/// The serialized form of `PatternElem`. Binary formats identify variants by their index, so new
+/// variants have to be added at the end.
+#[derive(Deserialize)]
+#[serde(rename = "PatternElem")]
+enum RawElem<T> {
+    Const(T),
+    Cube,
+    CubeRoot,
+    Custom(String),
+    Div(T),
+    Meta(Vec<RawElem<T>>),
+    Mod(T),
+    Mult(T),
+    Plus(T),
+    Pow(T),
+    Root(T),
+    Square,
+    SquareRoot,
+    Product(usize),
+    Sum(usize),
+    Linear(LinearRecurrence<T>),
+    Index(IndexFormula<T>),
+    Digit(DigitOp<T>, u32),
+    And(T),
+    Or(T),
+    RotateLeft(T),
+    ShiftLeft(T),
+    ShiftRight(T),
+    Xor(T),
+    MetaWithOperands(Vec<RawElem<T>>, Vec<RawElem<T>>),
+}
+
+impl<T: Number + Serialize> Serialize for PatternElem<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        const NAME: &str = "PatternElem";
+
+        match *self {
+            PatternElem::Const(ref n) => serializer.serialize_newtype_variant(NAME, 0, "Const", n),
+            PatternElem::Cube => serializer.serialize_unit_variant(NAME, 1, "Cube"),
+            PatternElem::CubeRoot => serializer.serialize_unit_variant(NAME, 2, "CubeRoot"),
+            PatternElem::Custom(ref custom) => serializer.serialize_newtype_variant(NAME, 3, "Custom", custom.name()),
+            PatternElem::Div(ref n) => serializer.serialize_newtype_variant(NAME, 4, "Div", n),
+            // The pattern describing the operands is only written if it was found along with the
+            // operations (see `Pattern::with_operand_pattern`).
+            PatternElem::Meta(ref pat) => match pat.explicit_operand_pattern() {
+                Some(operands) => {
+                    let mut variant = serializer.serialize_tuple_variant(NAME, 24, "MetaWithOperands", 2)?;
+                    variant.serialize_field(pat)?;
+                    variant.serialize_field(operands)?;
+                    variant.end()
+                }
+                None => serializer.serialize_newtype_variant(NAME, 5, "Meta", pat),
+            },
+            PatternElem::Mod(ref n) => serializer.serialize_newtype_variant(NAME, 6, "Mod", n),
+            PatternElem::Mult(ref n) => serializer.serialize_newtype_variant(NAME, 7, "Mult", n),
+            PatternElem::Plus(ref n) => serializer.serialize_newtype_variant(NAME, 8, "Plus", n),
+            PatternElem::Pow(ref n) => serializer.serialize_newtype_variant(NAME, 9, "Pow", n),
+            PatternElem::Root(ref n) => serializer.serialize_newtype_variant(NAME, 10, "Root", n),
+            PatternElem::Square => serializer.serialize_unit_variant(NAME, 11, "Square"),
+            PatternElem::SquareRoot => serializer.serialize_unit_variant(NAME, 12, "SquareRoot"),
+            PatternElem::Product(k) => serializer.serialize_newtype_variant(NAME, 13, "Product", &k),
+            PatternElem::Sum(k) => serializer.serialize_newtype_variant(NAME, 14, "Sum", &k),
+            PatternElem::Linear(ref linear) => serializer.serialize_newtype_variant(NAME, 15, "Linear", linear),
+            PatternElem::Index(ref formula) => serializer.serialize_newtype_variant(NAME, 16, "Index", formula),
+            PatternElem::Digit(ref op, radix) => {
+                let mut variant = serializer.serialize_tuple_variant(NAME, 17, "Digit", 2)?;
+                variant.serialize_field(op)?;
+                variant.serialize_field(&radix)?;
+                variant.end()
+            }
+            PatternElem::And(ref n) => serializer.serialize_newtype_variant(NAME, 18, "And", n),
+            PatternElem::Or(ref n) => serializer.serialize_newtype_variant(NAME, 19, "Or", n),
+            PatternElem::RotateLeft(ref n) => serializer.serialize_newtype_variant(NAME, 20, "RotateLeft", n),
+            PatternElem::ShiftLeft(ref n) => serializer.serialize_newtype_variant(NAME, 21, "ShiftLeft", n),
+            PatternElem::ShiftRight(ref n) => serializer.serialize_newtype_variant(NAME, 22, "ShiftRight", n),
+            PatternElem::Xor(ref n) => serializer.serialize_newtype_variant(NAME, 23, "Xor", n),
+        }
+    }
+}
+
+impl<T: Number + Serialize> Serialize for Pattern<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de, T: Number + Deserialize<'de>> Deserialize<'de> for PatternElem<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        CustomRegistry::new().deserialize_elem(deserializer)
+    }
+}
+
+impl<'de, T: Number + Deserialize<'de>> Deserialize<'de> for Pattern<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        CustomRegistry::new().deserialize_pattern(deserializer)
+    }
+}
+
+impl<T: Number> CustomRegistry<T> {
+    /// Deserializes a pattern, resolving the names of custom operations with the registry.
+    ///
+    /// ```
+    /// # #[macro_use] extern crate sea_canal;
+    /// # extern crate serde_json;
+    /// # use sea_canal::{CustomPatternElem, CustomRegistry, Pattern};
+    /// # use sea_canal::PatternElem::{Custom, Plus};
+    /// # fn main() {
+    /// fn even(_: i32, y: i32) -> bool { y % 2 == 0 }
+    ///
+    /// let pat = pat![Plus(3), Custom(CustomPatternElem::new(even, "even"))];
+    /// let json = serde_json::to_string(&pat).unwrap();
+    /// assert_eq!(r#"[{"Plus":3},{"Custom":"even"}]"#, json);
+    ///
+    /// let registry: CustomRegistry = vec![CustomPatternElem::new(even, "even")].into_iter().collect();
+    /// let mut deserializer = serde_json::Deserializer::from_str(&json);
+    /// assert_eq!(pat, registry.deserialize_pattern(&mut deserializer).unwrap());
+    /// # }
+    /// ```
+    pub fn deserialize_pattern<'de, D>(&self, deserializer: D) -> Result<Pattern<T>, D::Error>
+        where D: Deserializer<'de>, T: Deserialize<'de> {
+        self.resolve_all(Vec::<RawElem<T>>::deserialize(deserializer)?)
+    }
+
+    /// Deserializes a single operation, like `deserialize_pattern`.
+    pub fn deserialize_elem<'de, D>(&self, deserializer: D) -> Result<PatternElem<T>, D::Error>
+        where D: Deserializer<'de>, T: Deserialize<'de> {
+        self.resolve(RawElem::deserialize(deserializer)?)
+    }
+
+    fn resolve_all<E: Error>(&self, elems: Vec<RawElem<T>>) -> Result<Pattern<T>, E> {
+        elems.into_iter().map(|elem| self.resolve(elem)).collect()
+    }
+
+    fn resolve<E: Error>(&self, elem: RawElem<T>) -> Result<PatternElem<T>, E> {
+        Ok(match elem {
+            RawElem::Const(n) => PatternElem::Const(n),
+            RawElem::Cube => PatternElem::Cube,
+            RawElem::CubeRoot => PatternElem::CubeRoot,
+            RawElem::Custom(name) => match self.get(&name) {
+                Some(custom) => PatternElem::Custom(custom.clone()),
+                None => return Err(E::custom(format!("unknown custom operation `{}`", name))),
+            },
+            RawElem::Div(n) => PatternElem::Div(n),
+            RawElem::Meta(elems) => PatternElem::Meta(self.resolve_all(elems)?),
+            RawElem::MetaWithOperands(elems, operands) => {
+                let elems = self.resolve_all(elems)?.into_iter().collect();
+                PatternElem::Meta(Pattern::with_operand_pattern(elems, self.resolve_all(operands)?))
+            }
+            RawElem::Mod(n) => PatternElem::Mod(n),
+            RawElem::Mult(n) => PatternElem::Mult(n),
+            RawElem::Plus(n) => PatternElem::Plus(n),
+            // Exponents are normalized the same way as when operations are constructed.
+            RawElem::Pow(n) => PatternElem::pow(n).ok_or_else(|| E::custom("invalid exponent"))?,
+            RawElem::Root(n) => PatternElem::root(n).ok_or_else(|| E::custom("invalid exponent"))?,
+            RawElem::Square => PatternElem::Square,
+            RawElem::SquareRoot => PatternElem::SquareRoot,
+            RawElem::Product(k) if k >= 2 => PatternElem::Product(k),
+            RawElem::Sum(k) if k >= 2 => PatternElem::Sum(k),
+            RawElem::Product(_) | RawElem::Sum(_) => return Err(E::custom("invalid order")),
+            RawElem::Linear(ref linear) if linear.coefficients.is_empty() => return Err(E::custom("invalid order")),
+            RawElem::Linear(linear) => PatternElem::Linear(linear),
+            RawElem::Index(IndexFormula::Polynomial(ref coefficients)) if coefficients.is_empty() => {
+                return Err(E::custom("polynomial without coefficients"));
+            }
+            RawElem::Index(IndexFormula::Scaled { start, .. }) if start > MAX_START => return Err(E::custom("invalid start")),
+            RawElem::Index(formula) => PatternElem::Index(formula),
+            RawElem::Digit(op, radix) => {
+                // Operands are checked the same way as when they're replaced.
+                let op = match op.get_operand() {
+                    Some(operand) => op.with_operand(operand).ok_or_else(|| E::custom("invalid operand"))?,
+                    None => op,
+                };
+
+                PatternElem::Digit(op, radix)
+            }
+            RawElem::And(n) => PatternElem::And(n),
+            RawElem::Or(n) => PatternElem::Or(n),
+            RawElem::Xor(n) => PatternElem::Xor(n),
+            RawElem::RotateLeft(n) => with_places(PatternElem::RotateLeft(T::zero()), n)?,
+            RawElem::ShiftLeft(n) => with_places(PatternElem::ShiftLeft(T::zero()), n)?,
+            RawElem::ShiftRight(n) => with_places(PatternElem::ShiftRight(T::zero()), n)?,
+        })
+    }
+}
+
+/// Sets the number of places of a shift or rotation, which is checked the same way as when it's
+/// replaced.
+fn with_places<T: Number, E: Error>(elem: PatternElem<T>, n: T) -> Result<PatternElem<T>, E> {
+    elem.with_operand(n).ok_or_else(|| E::custom("invalid number of places"))
+}
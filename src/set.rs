@@ -0,0 +1,102 @@
+use iter::PatternIter;
+use number::Number;
+use pattern::{Pattern, PatternElem};
+
+/// The patterns of a given length that describe a sequence, represented by the set of operations
+/// that are valid at each position of the pattern rather than by every combination of them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PatternSet<T = i32> {
+    positions: Vec<Vec<PatternElem<T>>>,
+}
+
+impl<T: Number> PatternSet<T> {
+    pub(crate) fn new(mut positions: Vec<Vec<PatternElem<T>>>) -> Self {
+        for position in &mut positions {
+            position.sort();
+            position.dedup();
+        }
+
+        PatternSet { positions }
+    }
+
+    /// Returns the number of operations in each pattern of the set.
+    pub fn period(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Returns the operations that are valid at position `i` of the patterns, in ascending order.
+    pub fn position(&self, i: usize) -> &[PatternElem<T>] {
+        &self.positions[i]
+    }
+
+    pub fn positions(&self) -> &[Vec<PatternElem<T>>] {
+        &self.positions
+    }
+
+    /// Returns the number of patterns in the set, saturating at `usize::MAX`.
+    pub fn count(&self) -> usize {
+        self.positions.iter().fold(1, |count, position| count.saturating_mul(position.len()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.iter().any(Vec::is_empty)
+    }
+
+    pub fn contains(&self, pat: &Pattern<T>) -> bool {
+        pat.len() == self.period() &&
+            pat.iter().zip(&self.positions).all(|(elem, position)| position.binary_search(elem).is_ok())
+    }
+
+    /// Picks a single pattern to stand for the whole set, which is the greatest one (and the one
+    /// that `Analyzer::find_any_pattern_of_length` returns).
+    pub fn representative(&self) -> Option<Pattern<T>> {
+        if self.is_empty() {
+            return None;
+        }
+
+        Some(self.positions.iter().filter_map(|position| position.last().cloned()).collect())
+    }
+
+    /// Picks the simplest pattern in the set, i.e. the one with the lowest cost (see
+    /// `Pattern::cost`). Ties are broken by choosing the least pattern.
+    pub fn best(&self) -> Option<Pattern<T>> {
+        // The cost of a pattern is the sum of the costs of its operations, so the cheapest one is
+        // made of the cheapest operation at each position.
+        self.positions.iter().map(|position| position.iter().min_by_key(|elem| elem.cost()).cloned()).collect()
+    }
+
+    /// Constructs every pattern in the set, ordered from simplest to most complex.
+    pub fn ranked(&self) -> Vec<Pattern<T>> {
+        let mut pats: Vec<_> = self.iter().map(|pat| (pat.cost(), pat)).collect();
+        pats.sort();
+        pats.into_iter().map(|(_, pat)| pat).collect()
+    }
+
+    /// Lazily iterates over the patterns in the set, in ascending order.
+    pub fn iter(&self) -> PatternIter<T> {
+        PatternIter::new(self.positions.clone())
+    }
+
+    /// Constructs every pattern in the set.
+    pub fn expand(&self) -> Vec<Pattern<T>> {
+        self.iter().collect()
+    }
+}
+
+impl<T: Number> IntoIterator for PatternSet<T> {
+    type Item = Pattern<T>;
+    type IntoIter = PatternIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        PatternIter::new(self.positions)
+    }
+}
+
+impl<T: Number> IntoIterator for &PatternSet<T> {
+    type Item = Pattern<T>;
+    type IntoIter = PatternIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
@@ -6,7 +6,7 @@ pub struct Stepper {
 
 impl Stepper {
     pub fn new(start: usize, end: usize, step: usize) -> Self {
-        Stepper { start: start, end: end, step: step }
+        Stepper { start, end, step }
     }
 }
 
@@ -0,0 +1,242 @@
+use std::collections::HashSet;
+
+use analyzer::find_best_pattern;
+use builder::{AnalyzerBuilder, OverflowPolicy};
+use choice::{MAX_ORDER, PatternElemChoice, intersection, is_tested};
+use error::AnalyzerError;
+use meta::MetaAnalyzer;
+use number::Number;
+use pattern::{Pattern, PatternElem};
+use set::PatternSet;
+use stepper::Stepper;
+
+/// Identifies patterns that describe a sequence whose terms arrive one at a time, e.g. from a live
+/// feed. It finds the same patterns as an `Analyzer` given every term pushed so far.
+///
+/// The operations valid at each position of the patterns of up to `max` operations are kept up to
+/// date as terms are pushed, by narrowing them to the ones that also describe the newest
+/// transition, so querying them doesn't re-analyze the sequence. Longer patterns and meta-patterns
+/// are found from the transitions when they're asked for.
+///
+/// ```
+/// # #[macro_use] extern crate sea_canal;
+/// # use sea_canal::{Pattern, StreamingAnalyzer};
+/// # use sea_canal::PatternElem::{Mult, Plus};
+/// # fn main() {
+/// let mut analyzer = StreamingAnalyzer::new(2);
+///
+/// for &n in &[1, 2, 4] {
+///     analyzer.push(n).unwrap();
+/// }
+///
+/// assert!(analyzer.pattern_set(1).contains(&pat![Mult(2)]));
+/// assert!(analyzer.pattern_set(2).contains(&pat![Plus(1), Plus(2)]));
+///
+/// analyzer.push(8).unwrap();
+///
+/// assert_eq!(vec![pat![Mult(2)]], analyzer.find_patterns_of_length(1));
+/// assert!(!analyzer.pattern_set(2).contains(&pat![Plus(1), Plus(2)]));
+/// # }
+/// ```
+pub struct StreamingAnalyzer<T = i32> {
+    options: AnalyzerBuilder<T>,
+    /// The last `MAX_ORDER` terms, which are all that later transitions depend on.
+    recent: Vec<T>,
+    len: usize,
+    largest: T,
+    mod_bound: T,
+    choices: Vec<PatternElemChoice<T>>,
+    /// The transitions that leave a term unchanged (and the terms up to it), whose moduli depend
+    /// on `mod_bound`.
+    unchanged: Vec<(usize, Vec<T>)>,
+    /// The operations valid at each position of a pattern of `n` operations, at index `n - 1`.
+    /// Positions that no transition has reached yet are left out.
+    positions: Vec<Vec<HashSet<PatternElem<T>>>>,
+}
+
+impl<T: Number> StreamingAnalyzer<T> {
+    /// Creates an analyzer with the default options (see `AnalyzerBuilder::new`), which keeps
+    /// track of the patterns of up to `max` operations.
+    pub fn new(max: usize) -> Self {
+        Self::with_builder(AnalyzerBuilder::new(), max)
+    }
+
+    pub(crate) fn with_builder(options: AnalyzerBuilder<T>, max: usize) -> Self {
+        StreamingAnalyzer {
+            options,
+            recent: Vec::new(),
+            len: 0,
+            largest: T::zero(),
+            mod_bound: T::zero(),
+            choices: Vec::new(),
+            unchanged: Vec::new(),
+            positions: vec![Vec::new(); max],
+        }
+    }
+
+    /// Returns the number of terms pushed so far.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Adds the next term of the sequence. Fails, leaving the analyzer unchanged, if the difference
+    /// from the previous term overflows and the overflow policy is `OverflowPolicy::Reject`.
+    pub fn push(&mut self, value: T) -> Result<(), AnalyzerError> {
+        if let Some(last) = self.recent.last() {
+            if self.options.overflow == OverflowPolicy::Reject && value.checked_sub(last).is_none() {
+                return Err(AnalyzerError::Overflow { index: self.len - 1 });
+            }
+        }
+
+        if let Some(abs) = value.checked_abs() {
+            if abs > self.largest {
+                self.largest = abs;
+                self.raise_mod_bound();
+            }
+        }
+
+        if !self.recent.is_empty() {
+            let index = self.choices.len();
+            let choice = PatternElemChoice::from_window(&self.recent, &value, &self.mod_bound, &self.options);
+
+            for (n, positions) in self.positions.iter_mut().enumerate() {
+                let i = index % (n + 1);
+
+                if i < positions.len() {
+                    // The recurrences that the position's first transition didn't determine.
+                    if index == i + n + 1 {
+                        positions[i].extend(choice.0.iter().filter(|elem| elem.order() > i + 1).cloned());
+                    }
+
+                    positions[i].retain(|elem| choice.admits(elem, index));
+                } else {
+                    positions.push(choice.0.clone());
+                }
+            }
+
+            if self.recent.last() == Some(&value) {
+                let mut terms = self.recent.clone();
+                terms.push(value.clone());
+                self.unchanged.push((index, terms));
+            }
+
+            self.choices.push(choice);
+        }
+
+        if self.recent.len() == MAX_ORDER {
+            self.recent.remove(0);
+        }
+
+        self.recent.push(value);
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Finds the operations that are valid at each position of a pattern of `n` operations
+    /// describing the terms pushed so far, like `Analyzer::pattern_set`.
+    pub fn pattern_set(&self, n: usize) -> PatternSet<T> {
+        let mut positions = Vec::with_capacity(n);
+
+        for i in 0..n {
+            let mut position: Vec<_> = match self.positions.get(n - 1) {
+                Some(cached) => cached.get(i).map(|set| set.iter().cloned().collect()).unwrap_or_default(),
+                None => intersection(step!(i => self.choices.len(); n).map(|j| (j, &self.choices[j]))).into_iter().collect(),
+            };
+            let last = step!(i => self.choices.len(); n).last().unwrap_or(0);
+            position.retain(|elem| is_tested(elem, last, self.len));
+
+            if self.options.meta_depth > 0 {
+                position.extend(self.find_meta_patterns(i, n));
+            }
+
+            let is_empty = position.is_empty();
+            positions.push(position);
+
+            if is_empty {
+                positions.resize(n, Vec::new());
+                break;
+            }
+        }
+
+        PatternSet::new(positions)
+    }
+
+    /// Finds the set of patterns of minimal size (up to `max` operations) that describe the terms
+    /// pushed so far, like `Analyzer::find_pattern_set`.
+    pub fn find_pattern_set(&self, max: usize) -> Option<PatternSet<T>> {
+        (1..max + 1).map(|i| self.pattern_set(i)).find(|set| !set.is_empty())
+    }
+
+    /// Attempts to find exactly one pattern of `n` operations that describes the terms pushed so
+    /// far, like `Analyzer::find_any_pattern_of_length`.
+    pub fn find_any_pattern_of_length(&self, n: usize) -> Option<Pattern<T>> {
+        self.pattern_set(n).representative()
+    }
+
+    /// Attempts to find exactly one pattern of maximum size `max`, like
+    /// `Analyzer::find_any_pattern`.
+    pub fn find_any_pattern(&self, max: usize) -> Option<Pattern<T>> {
+        (1..max + 1).filter_map(|i| self.find_any_pattern_of_length(i)).next()
+    }
+
+    /// Finds the simplest pattern of `n` operations, like `Analyzer::find_best_pattern_of_length`.
+    pub fn find_best_pattern_of_length(&self, n: usize) -> Option<Pattern<T>> {
+        self.pattern_set(n).best()
+    }
+
+    /// Finds the simplest pattern of maximum size `max`, like `Analyzer::find_best_pattern`.
+    pub fn find_best_pattern(&self, max: usize) -> Option<Pattern<T>> {
+        find_best_pattern(max, |i| self.find_best_pattern_of_length(i))
+    }
+
+    /// Finds all patterns with `n` operations, like `Analyzer::find_patterns_of_length`.
+    pub fn find_patterns_of_length(&self, n: usize) -> Vec<Pattern<T>> {
+        self.pattern_set(n).into_iter().collect()
+    }
+
+    /// Finds the patterns of minimal size (up to `max` operations), like
+    /// `Analyzer::find_patterns`.
+    pub fn find_patterns(&self, max: usize) -> Vec<Pattern<T>> {
+        self.find_pattern_set(max).map(|set| set.into_iter().collect()).unwrap_or_default()
+    }
+
+    /// Recomputes the transitions that leave a term unchanged after the largest magnitude in the
+    /// sequence grows, since their moduli are bounded by it. Any new modulus is larger than every
+    /// earlier term, so no other transition has it, and only the positions holding those
+    /// transitions need to be recomputed.
+    fn raise_mod_bound(&mut self) {
+        let mod_bound = self.options.mod_bound(self.largest.clone());
+
+        if mod_bound == self.mod_bound {
+            return;
+        }
+
+        self.mod_bound = mod_bound;
+
+        for &(index, ref terms) in &self.unchanged {
+            let (value, prev) = terms.split_last().expect("transitions have two terms");
+            self.choices[index] = PatternElemChoice::from_window(prev, value, &self.mod_bound, &self.options);
+        }
+
+        for n in 1..self.positions.len() + 1 {
+            let mut offsets: Vec<_> = self.unchanged.iter().map(|&(index, _)| index % n).collect();
+            offsets.sort();
+            offsets.dedup();
+
+            for i in offsets {
+                self.positions[n - 1][i] = intersection(step!(i => self.choices.len(); n).map(|j| (j, &self.choices[j])));
+            }
+        }
+    }
+
+    fn find_meta_patterns(&self, offset: usize, n: usize) -> Vec<PatternElem<T>> {
+        let choices: Vec<_> = step!(offset => self.choices.len(); n).map(|i| self.choices[i].clone()).collect();
+        let meta_analyzer = MetaAnalyzer::new(choices, &self.options);
+
+        meta_analyzer.find_patterns().into_iter().map(PatternElem::Meta).collect()
+    }
+}
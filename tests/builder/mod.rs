@@ -0,0 +1,121 @@
+use sea_canal::{Analyzer, AnalyzerBuilder, AnalyzerError, CustomPatternElem, DigitOp, Operation, OverflowPolicy};
+use sea_canal::Pattern;
+use sea_canal::PatternElem::*;
+
+#[test]
+fn default_builder() {
+    let slice = &[7, 1, 3, 9, 3, 5, 25, 19];
+    let analyzer = AnalyzerBuilder::new().build(slice).unwrap();
+
+    assert_eq!(Analyzer::from_slice(slice).find_patterns(4), analyzer.find_patterns(4));
+}
+
+#[test]
+fn disable_operations() {
+    let analyzer = AnalyzerBuilder::new().disable(Operation::Square)
+                                         .disable(Operation::SquareRoot)
+                                         .build(&[2, 4, 2, 4])
+                                         .unwrap();
+
+    assert_eq!(Some(pat![Plus(2), Plus(-2)]), analyzer.find_any_pattern(2));
+}
+
+#[test]
+fn only_operations() {
+    let analyzer = AnalyzerBuilder::new().operations(&[Operation::Mult, Operation::Div]).build(&[2, 4, 2, 4]).unwrap();
+
+    assert_eq!(vec![pat![Mult(2), Div(2)]], analyzer.find_patterns(2));
+    assert_eq!(Some(pat![Mult(2), Div(2)]), analyzer.find_any_pattern(2));
+}
+
+#[test]
+fn meta_depth() {
+    let slice = &[1, 2, 4, 7, 11];
+
+    assert_eq!(None, AnalyzerBuilder::new().build(slice).unwrap().find_any_pattern(1));
+    assert_eq!(
+        Some(pat!(Meta(pat!(Plus(1), Plus(2), Plus(3), Plus(4))))),
+        AnalyzerBuilder::new().meta_depth(1).build(slice).unwrap().find_any_pattern(1)
+    );
+}
+
+fn pow4(i: i32, j: i32) -> bool {
+    i.checked_pow(4) == Some(j)
+}
+
+#[test]
+fn custom_pattern() {
+    let pow4_pattern = CustomPatternElem::new(pow4, "^4");
+    let analyzer = AnalyzerBuilder::new().custom_pattern(pow4_pattern.clone())
+                                         .disable(Operation::Pow)
+                                         .build(&[2, 16, 17, 83521])
+                                         .unwrap();
+
+    assert_eq!(Some(pat![Custom(pow4_pattern), Plus(1)]), analyzer.find_any_pattern(2));
+}
+
+#[test]
+fn reject_overflow() {
+    let slice = &[0, i32::MIN, i32::MAX];
+
+    assert_eq!(
+        Some(AnalyzerError::Overflow { index: 1 }),
+        AnalyzerBuilder::new().overflow(OverflowPolicy::Reject).build(slice).err()
+    );
+    assert!(AnalyzerBuilder::new().overflow(OverflowPolicy::Omit).build(slice).is_ok());
+    assert!(AnalyzerBuilder::new().overflow(OverflowPolicy::Reject).build(&[0i64, i32::MIN.into(), i32::MAX.into()]).is_ok());
+}
+
+#[test]
+fn max_modulus() {
+    let slice = &[17, 5, 2, 2];
+
+    assert!(AnalyzerBuilder::new().build(slice).unwrap().find_patterns(2).contains(&pat![Mod(12), Mod(3)]));
+    assert_eq!(
+        vec![pat![Mod(6), Mod(3)], pat![Mod(6), Plus(-3)]],
+        AnalyzerBuilder::new().operations(&[Operation::Plus, Operation::Mod]).max_modulus(10).build(slice).unwrap().find_patterns(2)
+    );
+}
+
+#[test]
+fn reject_short_sequences() {
+    assert_eq!(Some(AnalyzerError::TooShort { len: 0 }), AnalyzerBuilder::<i32>::new().build(&[]).err());
+    assert_eq!(Some(AnalyzerError::TooShort { len: 1 }), AnalyzerBuilder::new().build(&[5]).err());
+    assert_eq!("at least two terms are needed, but only one was given", format!("{}", AnalyzerError::TooShort { len: 1 }));
+}
+
+#[test]
+fn digit_operations() {
+    let slice = &[12, 15, 21, 24, 30, 33];
+    let analyzer = AnalyzerBuilder::new().digits(10).build(slice).unwrap();
+
+    assert_eq!(None, Analyzer::from_slice(slice).find_any_pattern(1));
+    assert_eq!(Some(pat![Digit(DigitOp::PlusSum, 10)]), analyzer.find_best_pattern(1));
+    assert_eq!(vec![39, 51], analyzer.find_best_pattern(1).unwrap().predict_next(slice, 2));
+
+    // Reversing the bits of a number, then adding 1.
+    let analyzer = AnalyzerBuilder::new().digits(2).build(&[11, 13, 14, 7, 8, 1, 2]).unwrap();
+    let pat = analyzer.find_best_pattern(2).unwrap();
+
+    assert_eq!(pat![Digit(DigitOp::Reverse, 2), Plus(1)], pat);
+    assert_eq!("reverse (base 2), +1", format!("{}", pat));
+}
+
+#[test]
+fn bitwise_operations() {
+    // Toggling the lowest bit.
+    let slice = &[6, 7, 6, 7];
+
+    assert_eq!(Some(pat![Xor(1)]), AnalyzerBuilder::new().bitwise().build(slice).unwrap().find_any_pattern(1));
+    assert!(Analyzer::from_slice(slice).find_patterns(1).is_empty());
+
+    // Clearing and setting the second bit.
+    let analyzer = AnalyzerBuilder::new().operations(&[Operation::And, Operation::Or]).build(&[15, 13, 15, 13]).unwrap();
+    assert_eq!(vec![pat![And(-3), Or(2)]], analyzer.find_patterns(2));
+
+    let slice = &[13, 26, 52, 104];
+    let analyzer = AnalyzerBuilder::new().operations(&[Operation::ShiftLeft, Operation::ShiftRight]).build(slice).unwrap();
+    assert_eq!(vec![pat![ShiftLeft(1)]], analyzer.find_patterns(1));
+    assert_eq!(Some(pat![RotateLeft(4)]),
+               AnalyzerBuilder::new().operations(&[Operation::RotateLeft]).build(&[1u8, 16, 1, 16]).unwrap().find_any_pattern(1));
+}
@@ -1,5 +1,5 @@
 use sea_canal::Analyzer;
-use sea_canal::{CustomPatternElem, Pattern};
+use sea_canal::{AnalyzerBuilder, CustomOp, CustomPatternElem, Operation, Pattern};
 use sea_canal::PatternElem::*;
 
 fn pow4(i: i32, j: i32) -> bool {
@@ -18,5 +18,82 @@ fn find_any_pattern() {
     let slice = &[1, 2, 16, 2, 3, 81, 3];
     let analyzer = Analyzer::with_custom_patterns(slice, vec![pow4_pattern.clone(), root4_pattern.clone()]);
 
-    assert_eq!(Some(pat![Plus(1), Custom(pow4_pattern), Custom(root4_pattern)]), analyzer.find_any_pattern(4));
+    // The built-in `Pow(4)` and `Root(4)` describe the sequence too.
+    assert!(analyzer.find_patterns(4).contains(&pat![Plus(1), Custom(pow4_pattern), Custom(root4_pattern)]));
+    assert_eq!(Some(pat![Plus(1), Pow(4), Root(4)]), analyzer.find_any_pattern(4));
+}
+
+fn apply_pow4(i: i32) -> Option<i32> {
+    i.checked_pow(4)
+}
+
+#[test]
+fn predict_next() {
+    let pow4_pattern = CustomPatternElem::with_apply(pow4, apply_pow4, "^4");
+
+    let slice = &[1, 2, 16, 17];
+    let pat = pat![Plus(1), Custom(pow4_pattern)];
+
+    assert_eq!(vec![83521, 83522], pat.predict_next(slice, 2));
+    assert_eq!(Vec::<i32>::new(), pat![Custom(CustomPatternElem::new(pow4, "^4"))].predict_next(slice, 2));
+}
+
+#[test]
+fn custom_anomalies() {
+    let pow4_pattern = CustomPatternElem::new(pow4, "^4");
+    let pat = pat![Custom(pow4_pattern.clone()), Plus(1)];
+    let anomalies = pat.anomalies(&[1, 1, 2, 16, 17, 5]);
+
+    assert_eq!(1, anomalies.len());
+    assert_eq!(5, anomalies[0].index);
+    assert_eq!(Some(Custom(pow4_pattern)), anomalies[0].elem);
+    assert_eq!(None, anomalies[0].expected);
+    assert_eq!("term 5: expected ^4, found 5", format!("{}", anomalies[0]));
+}
+
+#[test]
+fn custom_matches() {
+    let pat = pat![Custom(CustomPatternElem::new(pow4, "^4")), Plus(1)];
+
+    assert!(pat.matches(&[1, 1, 2, 16, 17]));
+    assert_eq!(Some(5), pat.first_mismatch(&[1, 1, 2, 16, 17, 5]));
+}
+
+#[test]
+fn closure_patterns() {
+    let pows: Vec<_> = (5..8).map(|n| {
+        CustomPatternElem::new(move |x: i32, y| x.checked_pow(n) == Some(y), &format!("pow {}", n))
+    }).collect();
+    let analyzer = AnalyzerBuilder::new().disable(Operation::Pow)
+                                         .custom_patterns(pows.clone())
+                                         .build(&[2, 32, 2, 128])
+                                         .unwrap();
+
+    assert!(analyzer.find_patterns(3).contains(&pat![Custom(pows[0].clone()), Const(2), Custom(pows[2].clone())]));
+}
+
+struct Shift(u32);
+
+impl CustomOp for Shift {
+    fn check(&self, x: i32, y: i32) -> bool {
+        self.apply(x) == Some(y)
+    }
+
+    fn apply(&self, x: i32) -> Option<i32> {
+        x.checked_shl(self.0)
+    }
+
+    fn name(&self) -> String {
+        format!("<<{}", self.0)
+    }
+}
+
+#[test]
+fn custom_op() {
+    let shift = CustomPatternElem::from_op(Shift(3));
+    let pat = pat![Custom(shift.clone()), Plus(-1)];
+
+    assert_eq!("<<3, -1", format!("{}", pat));
+    assert_eq!(vec![56, 55, 440], pat.predict_next(&[1, 8, 7], 3));
+    assert_eq!(shift, CustomPatternElem::new(|_, _| false, "<<3"));
 }
@@ -0,0 +1,51 @@
+use sea_canal::{Analyzer, Tolerance};
+use sea_canal::Pattern;
+use sea_canal::PatternElem::*;
+
+#[test]
+fn exact_sequence_has_no_outliers() {
+    let slice = &[2, 4, 2, 4, 2];
+    let fuzzy = Analyzer::from_slice(slice).find_fuzzy_pattern(2, Tolerance::AllBut(0)).unwrap();
+
+    assert_eq!(pat![Plus(2), Plus(-2)], fuzzy.pattern);
+    assert!(fuzzy.outliers.is_empty());
+}
+
+#[test]
+fn single_corrupted_term() {
+    let slice = &[3, 6, 9, 12, 100, 18, 21, 24, 27, 30];
+    let analyzer = Analyzer::from_slice(slice);
+
+    assert_eq!(None, analyzer.find_any_pattern(2));
+    assert_eq!(None, analyzer.find_fuzzy_pattern(2, Tolerance::AllBut(1)));
+
+    let fuzzy = analyzer.find_fuzzy_pattern(2, Tolerance::AllBut(2)).unwrap();
+
+    assert_eq!(pat![Plus(3)], fuzzy.pattern);
+    assert_eq!(vec![3, 4], fuzzy.outliers);
+}
+
+#[test]
+fn fraction_tolerance() {
+    let slice = &[1, 2, 4, 8, 16, 33, 64, 128, 256, 512, 1024];
+    let analyzer = Analyzer::from_slice(slice);
+
+    assert_eq!(None, analyzer.find_fuzzy_pattern(1, Tolerance::Fraction(0.9)));
+
+    let fuzzy = analyzer.find_fuzzy_pattern(1, Tolerance::Fraction(0.8)).unwrap();
+
+    assert_eq!(pat![Mult(2)], fuzzy.pattern);
+    assert_eq!(vec![4, 5], fuzzy.outliers);
+
+    // NaN doesn't let any outliers through.
+    assert_eq!(None, analyzer.find_fuzzy_pattern(1, Tolerance::Fraction(f64::NAN)));
+}
+
+#[test]
+fn fuzzy_pattern_with_several_operations() {
+    let slice = &[10, 20, 15, 30, 25, 50, 45, 91, 85, 170];
+    let fuzzy = Analyzer::from_slice(slice).find_fuzzy_pattern(3, Tolerance::AllBut(2)).unwrap();
+
+    assert_eq!(pat![Mult(2), Plus(-5)], fuzzy.pattern);
+    assert_eq!(vec![6, 7], fuzzy.outliers);
+}
@@ -1,6 +1,14 @@
 #[macro_use]
 extern crate sea_canal;
+#[cfg(feature = "serde")]
+extern crate serde_json;
 
+mod builder;
 mod custom;
+mod fuzzy;
 mod meta;
+mod numeric;
+#[cfg(feature = "serde")]
+mod serialize;
 mod standard;
+mod stream;
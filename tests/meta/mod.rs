@@ -1,4 +1,4 @@
-use sea_canal::Analyzer;
+use sea_canal::{Analyzer, AnalyzerBuilder, DigitOp, Operation};
 use sea_canal::Pattern;
 use sea_canal::PatternElem::*;
 
@@ -32,3 +32,209 @@ fn mixed_operand_meta_pattern() {
     assert_eq!(None, analyzer.find_any_pattern(1));
     assert_eq!(Some(pat!(Meta(pat!(Plus(1), Mult(2), Plus(3), Mult(4))), Const(10))), analyzer.find_any_pattern(4));
 }
+
+#[test]
+fn meta_predict_next() {
+    let slice = &[1, 2, 4, 7, 11];
+    let pat = Analyzer::with_meta(slice).find_any_pattern(1).unwrap();
+
+    assert_eq!(vec![16, 22, 29], pat.predict_next(slice, 3));
+}
+
+#[test]
+fn meta_predict_next_resumes_phase() {
+    let slice = &[10, 11, 10, 20, 10, 13, 10, 40, 10];
+    let pat = pat!(Meta(pat!(Plus(1), Mult(2), Plus(3), Mult(4))), Const(10));
+
+    assert_eq!(vec![15, 10, 60, 10, 17], pat.predict_next(slice, 5));
+    assert_eq!(Some(Mult(6)), pat.elem_at(10));
+}
+
+#[test]
+fn modulus_meta_pattern() {
+    let slice = &[100, 0, 100, 1, 100, 4, 100, 2, 100];
+    let analyzer = Analyzer::with_meta(slice);
+
+    assert!(analyzer.find_patterns_of_length(2).contains(&pat!(Meta(pat!(Mod(10), Mod(9), Mod(8), Mod(7))), Const(100))));
+}
+
+#[test]
+fn meta_find_best_pattern() {
+    let slice = &[10, 11, 10, 12, 10, 13, 10];
+    let analyzer = Analyzer::with_meta(slice);
+
+    assert_eq!(Some(pat!(Meta(pat!(Plus(1), Plus(2), Plus(3))), Const(10))), analyzer.find_best_pattern(2));
+    assert_eq!(Some(pat![Plus(2), Plus(-2)]), Analyzer::with_meta(&[2, 4, 2, 4, 2]).find_best_pattern(2));
+}
+
+#[test]
+fn nested_meta_pattern() {
+    // The differences are 1, 2, 4, 7, 11, whose differences are 1, 2, 3, 4.
+    let slice = &[0, 1, 3, 7, 14, 25];
+    let inner = pat!(Plus(1), Plus(2), Plus(4), Plus(7), Plus(11));
+
+    assert_eq!(None, Analyzer::with_meta(slice).find_any_pattern(1));
+    assert_eq!(
+        Some(pat!(Meta(inner.clone()))),
+        AnalyzerBuilder::new().meta_depth(2).build(slice).unwrap().find_any_pattern(1)
+    );
+    assert_eq!(vec![41, 63, 92], pat!(Meta(inner)).predict_next(slice, 3));
+}
+
+#[test]
+fn cubic_sequence() {
+    let slice: Vec<i64> = (0..8).map(|i| i * i * i).collect();
+    let analyzer = AnalyzerBuilder::new().meta_depth(2).build(&slice).unwrap();
+    let pat = analyzer.find_any_pattern(1).unwrap();
+
+    assert_eq!(vec![512, 729, 1000], pat.predict_next(&slice, 3));
+    assert_eq!(None, AnalyzerBuilder::new().meta_depth(1).build(&slice).unwrap().find_any_pattern(1));
+}
+
+#[test]
+fn meta_pattern_scales() {
+    // The operands of the meta-pattern are the 200 numbers from 1 to 200.
+    let slice: Vec<i64> = (0..201).map(|i| i * (i + 1) / 2).collect();
+    let analyzer = Analyzer::with_meta(&slice);
+    let pat = analyzer.find_any_pattern(1).unwrap();
+
+    assert_eq!(pat!(Meta((1..201).map(Plus).collect())), pat);
+    assert_eq!(vec![20301, 20503], pat.predict_next(&slice, 2));
+
+    let slice: Vec<i64> = (0..300).map(|i| if i % 2 == 0 { 10 } else { 10 + (i + 1) / 2 }).collect();
+    let pats = Analyzer::with_meta(&slice).find_patterns_of_length(2);
+
+    assert!(pats.contains(&pat!(Meta((1..151).map(Plus).collect()), Const(10))));
+}
+
+#[test]
+fn nested_meta_pattern_scales() {
+    // Every transition from 12 to 0 is a modulus by any of the six divisors of 12, so trying every
+    // combination of them would never finish.
+    let slice: Vec<i64> = (0..16).map(|i| if i % 2 == 0 { 12 } else { 0 }).collect();
+    let analyzer = AnalyzerBuilder::new().meta_depth(2).build(&slice).unwrap();
+    let pat = analyzer.find_any_pattern(1).unwrap();
+
+    assert_eq!(pat!(Meta((0..15).map(|i| Plus(if i % 2 == 0 { -12 } else { 12 })).collect())), pat);
+    // The operands alternate between 1 and 2, which takes a meta-pattern of its own.
+    let moduli = pat!(Meta((0..8).map(|i| Mod(1 + i % 2)).collect()), Plus(12));
+    assert!(analyzer.find_patterns_of_length(2).contains(&moduli));
+}
+
+#[test]
+fn power_meta_pattern() {
+    let slice = &[2i128, 4, 64, 1 << 24, 1 << 120];
+    let pat = Analyzer::with_meta(slice).find_any_pattern(1).unwrap();
+
+    assert_eq!(pat!(Meta(pat!(Square, Cube, Pow(4), Pow(5)))), pat);
+    assert_eq!(Some(Pow(6)), pat.elem_at(4));
+    assert_eq!("[^2, ^3, ^4, ^5...]", format!("{}", pat));
+}
+
+#[test]
+fn alternating_power_meta_pattern() {
+    let slice = &[3, 9, 3, 9, 3];
+    let pats = Analyzer::with_meta(slice).find_patterns_of_length(1);
+
+    assert!(pats.contains(&pat!(Meta(pat!(Square, SquareRoot, Square, SquareRoot)))));
+    assert!(pats.contains(&pat!(Meta(pat!(Plus(6), Plus(-6), Plus(6), Plus(-6))))));
+    assert!(!pats.contains(&pat!(Meta(pat!(Square, Plus(-6), Square, Plus(-6))))));
+}
+
+#[test]
+fn meta_pattern_periods() {
+    let partial = pat!(Plus(1), Pow(4), Plus(3), Plus(4));
+
+    // Search and evaluation agree that the period has to fit twice.
+    assert!(!partial.has_repeating_types());
+    assert_eq!(None, pat!(Meta(partial)).elem_at(4));
+    assert!(pat!(Pow(4), Pow(5), Pow(6)).has_repeating_types());
+    assert_eq!(Some(Pow(7)), pat!(Meta(pat!(Pow(4), Pow(5), Pow(6)))).elem_at(3));
+
+    // A period of one operation type is only paid for once.
+    assert_eq!(7, pat!(Meta(pat!(Plus(1), Plus(2), Plus(3)))).cost());
+}
+
+#[test]
+fn meta_operands_use_enabled_operations() {
+    // The differences are 17, 5, 5, 5, which only a modulus leads through once constants are
+    // disabled.
+    let slice = &[0, 17, 22, 27, 32];
+    let builder = AnalyzerBuilder::new().disable(Operation::Const).meta_depth(1);
+
+    assert_eq!(Some(pat!(Meta(pat!(Plus(17), Plus(5), Plus(5), Plus(5))))),
+               builder.build(slice).unwrap().find_any_pattern(1));
+    assert_eq!(None, builder.disable(Operation::Mod).build(slice).unwrap().find_any_pattern(1));
+}
+
+#[test]
+fn unpatterned_meta_operands() {
+    let pat: Pattern = "[+3, +1, +4, +1, +5, +9, +2, +6, +5, +3, +5, +8, +9...]".parse().unwrap();
+    let slice = &[0, 3, 4, 8, 9, 14, 23, 25, 31, 36, 39, 44, 52, 61, 70];
+
+    assert_eq!(29, pat.cost());
+    assert_eq!(Some(14), pat.first_mismatch(slice));
+}
+
+#[test]
+fn meta_anomalies() {
+    let pat = pat!(Meta(pat!(Plus(1), Plus(2), Plus(3))), Const(10));
+    let slice = &[10, 11, 10, 12, 10, 13, 10, 15, 10];
+    let anomalies = pat.anomalies(slice);
+
+    assert_eq!(1, anomalies.len());
+    assert_eq!(7, anomalies[0].index);
+    assert_eq!(Some(Plus(4)), anomalies[0].elem);
+    assert_eq!(Some(14), anomalies[0].expected);
+}
+
+#[test]
+fn meta_matches() {
+    let pat = pat!(Meta(pat!(Plus(1), Plus(2), Plus(3))), Const(10));
+
+    assert!(pat.matches(&[10, 11, 10, 12, 10, 13, 10, 14, 10]));
+    assert_eq!(Some(7), pat.first_mismatch(&[10, 11, 10, 12, 10, 13, 10, 15, 10]));
+}
+
+#[test]
+fn digit_meta_pattern() {
+    let slice = &[1, 11, 112, 1123, 11234];
+    let analyzer = AnalyzerBuilder::new().digits(10).meta_depth(1).build(slice).unwrap();
+    let concat = |c| Digit(DigitOp::Concat(c), 10);
+    let pat = analyzer.find_best_pattern(1).unwrap();
+
+    assert_eq!(pat![Meta(pat![concat(1), concat(2), concat(3), concat(4)])], pat);
+    assert_eq!(vec![112345], pat.predict_next(slice, 1));
+}
+
+#[test]
+fn shift_meta_pattern() {
+    let slice = &[1, 2, 8, 64, 1024];
+    let analyzer = AnalyzerBuilder::new().bitwise().meta_depth(1).build(slice).unwrap();
+
+    assert!(analyzer.find_patterns(1).contains(&pat![Meta(pat![ShiftLeft(1), ShiftLeft(2), ShiftLeft(3), ShiftLeft(4)])]));
+}
+
+#[test]
+fn meta_operand_pattern_round_trip() {
+    // The fourth powers take three levels of meta-patterns, which is deeper than the operands of
+    // a meta-pattern are described by default, so the found pattern has to carry its operands.
+    let slice: Vec<i64> = (0..9).map(|i| i * i * i * i).collect();
+    let pat = AnalyzerBuilder::new().meta_depth(3).build(&slice).unwrap().find_any_pattern(1).unwrap();
+    let parsed: Pattern<i64> = pat.to_string().parse().unwrap();
+
+    assert_eq!(vec![6561, 10000], pat.predict_next(&slice, 2));
+    assert_eq!(pat, parsed);
+    assert_eq!(pat.predict_next(&slice, 2), parsed.predict_next(&slice, 2));
+    assert_eq!(pat.cost(), parsed.cost());
+    assert!(pat.to_string().contains(" | [+14, +50, +110"));
+
+    // Without them, the operands are only described one level deep.
+    let bare = match pat.iter().next() {
+        Some(Meta(inner)) => pat![Meta(inner.iter().cloned().collect())],
+        _ => unreachable!(),
+    };
+
+    assert!(pat != bare);
+    assert_eq!(Vec::<i64>::new(), bare.predict_next(&slice, 2));
+}
@@ -0,0 +1,172 @@
+use sea_canal::{Analyzer, Approx, Rational};
+use sea_canal::Pattern;
+use sea_canal::PatternElem::*;
+
+#[test]
+fn find_any_pattern_i64() {
+    let slice = &[5_000_000_000i64, 15_000_000_000, 14_999_999_999, 44_999_999_997];
+    let analyzer = Analyzer::from_slice(slice);
+
+    assert_eq!(Some(pat![Mult(3), Plus(-1)]), analyzer.find_any_pattern(2));
+}
+
+#[test]
+fn find_any_pattern_u64() {
+    let slice = &[3u64, 9, 81, 6561, 43046721];
+    let analyzer = Analyzer::from_slice(slice);
+
+    assert_eq!(Some(pat![Square]), analyzer.find_any_pattern(2));
+}
+
+#[test]
+fn unsigned_omits_negative_operands() {
+    let slice = &[10u64, 4, 10, 4];
+    let analyzer = Analyzer::from_slice(slice);
+
+    assert_eq!(
+        vec![
+            pat![Const(4), Const(10)],
+            pat![Const(4), Plus(6)],
+            pat![Mod(6), Const(10)],
+            pat![Mod(6), Plus(6)],
+        ],
+        analyzer.find_patterns(2)
+    );
+}
+
+#[test]
+fn find_any_pattern_i128() {
+    let slice = &[1i128 << 100, 1 << 101, 1 << 102];
+    let analyzer = Analyzer::from_slice(slice);
+
+    assert_eq!(Some(pat![Mult(2)]), analyzer.find_any_pattern(1));
+    assert_eq!(vec![1 << 103, 1 << 104], pat![Mult(2)].predict_next(slice, 2));
+}
+
+#[test]
+fn meta_find_any_pattern_i64() {
+    let slice = &[1i64, 2, 4, 7, 11];
+    let analyzer = Analyzer::with_meta(slice);
+
+    assert_eq!(Some(pat!(Meta(pat!(Plus(1), Plus(2), Plus(3), Plus(4))))), analyzer.find_any_pattern(1));
+}
+
+#[cfg(feature = "bigint")]
+mod bigint {
+    extern crate num_bigint;
+
+    use self::num_bigint::BigInt;
+    use sea_canal::{Analyzer, AnalyzerBuilder};
+    use sea_canal::Pattern;
+    use sea_canal::PatternElem::*;
+
+    #[test]
+    fn find_any_pattern_bigint() {
+        let start = BigInt::from(u64::MAX) * BigInt::from(u64::MAX);
+        let slice: Vec<_> = (0..4).map(|i| &start * BigInt::from(1u64 << (10 * i))).collect();
+        let analyzer = Analyzer::from_slice(&slice);
+
+        assert_eq!(Some(pat![Mult(BigInt::from(1024))]), analyzer.find_any_pattern(2));
+        assert_eq!(vec![&slice[3] * BigInt::from(1024)], pat![Mult(BigInt::from(1024))].predict_next(&slice, 1));
+    }
+
+    #[test]
+    fn shifts_bigint() {
+        // Shifting a big integer left never overflows, so it has to stop once it passes the target.
+        let slice: Vec<_> = [5, 3, 5, 3].iter().map(|&n| BigInt::from(n)).collect();
+        let analyzer = AnalyzerBuilder::new().bitwise().build(&slice).unwrap();
+
+        assert_eq!(Some(pat![Xor(BigInt::from(6))]), analyzer.find_best_pattern(2));
+
+        let slice: Vec<_> = (0..4).map(|i| BigInt::from(3) << (100 * i)).collect();
+        let analyzer = AnalyzerBuilder::new().bitwise().build(&slice).unwrap();
+
+        assert!(analyzer.find_patterns(1).contains(&pat![ShiftLeft(BigInt::from(100))]));
+        assert_eq!(None, pat![ShiftRight(BigInt::from(1))].first_mismatch(&[slice[3].clone(), &slice[3] >> 1]));
+    }
+}
+
+fn ratio(numer: i64, denom: i64) -> Rational {
+    Rational::new(numer, denom).unwrap()
+}
+
+#[test]
+fn find_any_pattern_rational() {
+    let slice: Vec<_> = vec![ratio(8, 1), ratio(4, 1), ratio(2, 1), ratio(1, 1), ratio(1, 2)];
+    let analyzer = Analyzer::from_slice(&slice);
+
+    assert_eq!(vec![pat![Div(ratio(2, 1))], pat![Mult(ratio(1, 2))]], analyzer.find_patterns(2));
+    assert_eq!(vec![ratio(1, 4), ratio(1, 8)], pat![Div(ratio(2, 1))].predict_next(&slice, 2));
+}
+
+#[test]
+fn find_patterns_rational_plus() {
+    let slice: Vec<_> = vec![ratio(1, 3), ratio(2, 3), ratio(1, 3), ratio(2, 3)];
+    let analyzer = Analyzer::from_slice(&slice);
+
+    assert!(analyzer.find_patterns(2).contains(&pat![Plus(ratio(1, 3)), Plus(ratio(-1, 3))]));
+    assert_eq!("+1/3, -1/3", format!("{}", pat![Plus(ratio(1, 3)), Plus(ratio(-1, 3))]));
+}
+
+#[test]
+fn meta_find_any_pattern_rational() {
+    // Without the last term, this is also described by the sum of the previous two terms.
+    let slice: Vec<_> = vec![ratio(1, 1), ratio(3, 2), ratio(5, 2), ratio(4, 1), ratio(6, 1)];
+    let analyzer = Analyzer::with_meta(&slice);
+
+    assert_eq!(Some(pat!(Meta(pat!(Plus(ratio(1, 2)), Plus(ratio(1, 1)), Plus(ratio(3, 2)), Plus(ratio(2, 1)))))), analyzer.find_any_pattern(1));
+}
+
+#[test]
+fn find_any_pattern_approx() {
+    let slice: Vec<Approx> = [0.1, 0.3, 0.5, 0.7000000000000001, 0.9].iter().filter_map(|&x| Approx::new(x)).collect();
+    let analyzer = Analyzer::from_slice(&slice);
+
+    assert_eq!(Some(pat![Plus(Approx::new(0.2).unwrap())]), analyzer.find_any_pattern(1));
+    assert_eq!("+0.2", format!("{}", analyzer.find_any_pattern(1).unwrap()));
+}
+
+#[test]
+fn find_any_pattern_approx_halving() {
+    let slice: Vec<Approx> = [8.0, 4.0, 2.0, 1.0, 0.5].iter().filter_map(|&x| Approx::new(x)).collect();
+    let analyzer = Analyzer::from_slice(&slice);
+
+    assert_eq!(Some(pat![Mult(Approx::new(0.5).unwrap())]), analyzer.find_any_pattern(1));
+}
+
+#[test]
+fn linear_recurrence_rational() {
+    // Each term is the average of the previous two, so it also follows from the previous term
+    // alone: a(n) = -a(n - 1) / 2 + 4. Without fractions, there's no such recurrence.
+    let slice: Vec<_> = [0, 4, 2, 3].iter().map(|&n| ratio(n, 1))
+                                    .chain(vec![ratio(5, 2), ratio(11, 4), ratio(21, 8)])
+                                    .collect();
+    let recurrence = Analyzer::from_slice(&slice).find_linear_recurrence(2).unwrap();
+
+    assert_eq!(vec![ratio(-1, 2)], recurrence.coefficients);
+    assert_eq!(ratio(4, 1), recurrence.constant);
+    assert_eq!(None, Analyzer::from_slice(&[0, 4, 2, 3]).find_linear_recurrence(2));
+}
+
+#[test]
+fn fractional_linear_recurrence() {
+    // a(n) = a(n - 1) / 2, whose coefficient integers can't represent.
+    let halving = [64, 32, 16, 8, 4, 2, 1];
+    let slice: Vec<_> = halving.iter().map(|&n| ratio(n, 1)).collect();
+    let recurrence = Analyzer::from_slice(&slice).find_linear_recurrence(3).unwrap();
+
+    assert_eq!(vec![ratio(1, 2)], recurrence.coefficients);
+    assert_eq!(ratio(0, 1), recurrence.constant);
+    assert_eq!(None, Analyzer::from_slice(&halving).find_linear_recurrence(3));
+}
+
+#[test]
+fn parse_rational_and_approx() {
+    let pat = pat![Div(ratio(2, 1)), Plus(ratio(1, 2)), Mult(ratio(-3, 4)), Meta(pat![Plus(ratio(1, 3)), Plus(ratio(2, 3))])];
+    assert_eq!("/2, +1/2, *-3/4, [+1/3, +2/3...]", format!("{}", pat));
+    assert_eq!(Ok(pat.clone()), format!("{}", pat).parse());
+
+    let approx = |x| Approx::<10>::new(x).unwrap();
+    let pat = pat![Plus(approx(0.2)), Mult(approx(-0.5)), Const(approx(1e-7)), Square];
+    assert_eq!(Ok(pat.clone()), format!("{}", pat).parse());
+}
@@ -0,0 +1,67 @@
+use serde_json;
+
+use sea_canal::{CustomPatternElem, CustomRegistry, DigitOp, IndexFamily, IndexFormula, LinearRecurrence, Pattern, PatternElem};
+use sea_canal::PatternElem::*;
+
+fn reverse(x: i32, y: i32) -> bool {
+    x.to_string().chars().rev().collect::<String>().parse() == Ok(y)
+}
+
+#[test]
+fn json_round_trip() {
+    let cases = vec![
+        (pat![Plus(-4), Mod(6), Square, Root(5), Meta(pat![Plus(1), Meta(pat![Mult(2), Const(0)])])],
+         r#"[{"Plus":-4},{"Mod":6},"Square",{"Root":5},{"Meta":[{"Plus":1},{"Meta":[{"Mult":2},{"Const":0}]}]}]"#),
+        (pat![Sum(2), Linear(LinearRecurrence { coefficients: vec![2, 1], constant: -1 })],
+         r#"[{"Sum":2},{"Linear":{"coefficients":[2,1],"constant":-1}}]"#),
+        (pat![Index(IndexFormula::Polynomial(vec![1, 0, 2])),
+              Index(IndexFormula::Scaled { family: IndexFamily::Power(2), scale: 3, start: 0, offset: 1 })],
+         r#"[{"Index":{"Polynomial":[1,0,2]}},{"Index":{"Scaled":{"family":{"Power":2},"scale":3,"start":0,"offset":1}}}]"#),
+        (pat![Digit(DigitOp::Reverse, 10), Digit(DigitOp::Concat(1), 2)],
+         r#"[{"Digit":["Reverse",10]},{"Digit":[{"Concat":1},2]}]"#),
+        (pat![Xor(5), ShiftLeft(2), RotateLeft(1)], r#"[{"Xor":5},{"ShiftLeft":2},{"RotateLeft":1}]"#),
+        ("[+2, +4, +8... | +2, +4]".parse().unwrap(),
+         r#"[{"MetaWithOperands":[[{"Plus":2},{"Plus":4},{"Plus":8}],[{"Plus":2},{"Plus":4}]]}]"#),
+    ];
+
+    for (pat, json) in cases {
+        assert_eq!(json, serde_json::to_string(&pat).unwrap());
+        assert_eq!(pat, serde_json::from_str(json).unwrap());
+    }
+}
+
+#[test]
+fn validated_operations() {
+    assert_eq!(Square, serde_json::from_str::<PatternElem>(r#"{"Pow":2}"#).unwrap());
+
+    // One for each of the checks made when deserializing.
+    let invalid = [
+        r#"{"Root":1}"#,
+        r#"{"Sum":1}"#,
+        r#"{"Linear":{"coefficients":[],"constant":0}}"#,
+        r#"{"Index":{"Polynomial":[]}}"#,
+        r#"{"Index":{"Scaled":{"family":"Prime","scale":1,"start":200000,"offset":0}}}"#,
+        r#"{"Digit":[{"Rotate":0},10]}"#,
+        r#"{"ShiftRight":0}"#,
+    ];
+
+    for json in invalid.iter() {
+        assert!(serde_json::from_str::<PatternElem>(json).is_err(), "{}", json);
+    }
+}
+
+#[test]
+fn custom_by_name() {
+    let custom = CustomPatternElem::new(reverse, "reverse");
+    let pat = pat![Custom(custom.clone()), Plus(13)];
+    let json = serde_json::to_string(&pat).unwrap();
+
+    assert_eq!(r#"[{"Custom":"reverse"},{"Plus":13}]"#, json);
+
+    let err = serde_json::from_str::<Pattern>(&json).unwrap_err();
+    assert!(err.to_string().contains("unknown custom operation `reverse`"));
+
+    let registry: CustomRegistry = vec![custom].into_iter().collect();
+    let mut deserializer = serde_json::Deserializer::from_str(&json);
+    assert_eq!(pat, registry.deserialize_pattern(&mut deserializer).unwrap());
+}
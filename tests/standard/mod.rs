@@ -1,4 +1,4 @@
-use sea_canal::Analyzer;
+use sea_canal::{Analyzer, AnalyzerBuilder, Anomaly, IndexFamily, IndexFormula, LinearRecurrence, Operation};
 use sea_canal::Pattern;
 use sea_canal::PatternElem::*;
 
@@ -78,3 +78,267 @@ fn find_patterns() {
         analyzer.find_patterns(4)
     );
 }
+
+#[test]
+fn predict_next() {
+    let slice = &[1, 4, 3, 6, 5];
+    let pat = Analyzer::from_slice(slice).find_any_pattern(4).unwrap();
+
+    assert_eq!(vec![8, 7, 10], pat.predict_next(slice, 3));
+    assert_eq!(vec![7, 10], pat.predict(8, 5, 2));
+    assert_eq!(Vec::<i32>::new(), pat.predict_next(&[], 3));
+}
+
+#[test]
+fn predict_next_stops_on_overflow() {
+    let pat = pat![Mult(1000)];
+
+    assert_eq!(vec![1000, 1000000, 1000000000], pat.predict_next(&[1], 5));
+}
+
+#[test]
+fn find_modulus_patterns() {
+    let analyzer = Analyzer::from_slice(&[17, 5, 2, 2]);
+    let pats = analyzer.find_patterns(3);
+
+    assert!(pats.contains(&pat![Mod(6), Mod(3)]));
+    assert!(pats.contains(&pat![Mod(12), Mod(3)]));
+}
+
+#[test]
+fn find_clock_pattern() {
+    let slice = &[5, 6, 2, 3, 3, 4, 0, 1, 1, 2, 2];
+    let analyzer = Analyzer::from_slice(slice);
+
+    assert_eq!(vec![pat![Plus(1), Mod(4)]], analyzer.find_patterns(4));
+    assert_eq!(vec![3, 3, 4, 0], analyzer.find_patterns(4)[0].predict_next(slice, 4));
+}
+
+#[test]
+fn find_patterns_without_overflow() {
+    let analyzer = Analyzer::from_slice(&[50000, 7, i32::MAX, i32::MIN]);
+    let pats = analyzer.find_patterns_of_length(3);
+
+    assert!(!pats.is_empty());
+    assert!(pats.iter().all(|pat| pat.iter().last() == Some(&Const(i32::MIN))));
+    assert!(pats.iter().all(|pat| !pat.iter().any(|elem| *elem == Square)));
+}
+
+#[test]
+fn patterns_of_length() {
+    let slice = &[2, 4, 2, 4, 2];
+    let analyzer = Analyzer::from_slice(slice);
+
+    assert_eq!(analyzer.find_patterns_of_length(2), analyzer.patterns_of_length(2).collect::<Vec<_>>());
+    assert_eq!(0, analyzer.patterns_of_length(3).count());
+}
+
+#[test]
+fn patterns_of_length_is_lazy() {
+    // Each of the 20 positions can be described by 8 different operations.
+    let slice = &[1; 41];
+    let analyzer = Analyzer::from_slice(slice);
+    let mut pats = analyzer.patterns_of_length(20);
+
+    assert_eq!(Some(Pattern::new(vec![Const(1); 20])), pats.next());
+    assert_eq!(Some(Pattern::new(vec![SquareRoot; 20])), pats.next_back());
+    assert_eq!(Some(pat![SquareRoot]), analyzer.find_any_pattern_of_length(1));
+}
+
+#[test]
+fn pattern_set() {
+    let slice = &[2, 4, 2, 4, 2];
+    let analyzer = Analyzer::from_slice(slice);
+    let set = analyzer.pattern_set(2);
+
+    assert_eq!(2, set.period());
+    assert_eq!(&[Const(4), Mult(2), Plus(2), Square], set.position(0));
+    assert_eq!(&[Const(2), Div(2), Plus(-2), SquareRoot], set.position(1));
+    assert_eq!(16, set.count());
+    assert!(set.contains(&pat![Plus(2), Plus(-2)]));
+    assert!(!set.contains(&pat![Plus(2), Plus(2)]));
+    assert!(!set.contains(&pat![Plus(2)]));
+    assert_eq!(analyzer.find_any_pattern_of_length(2), set.representative());
+    assert_eq!(analyzer.find_patterns_of_length(2), set.expand());
+    assert!(analyzer.pattern_set(3).is_empty());
+    assert_eq!(None, analyzer.pattern_set(3).representative());
+}
+
+#[test]
+fn find_pattern_set() {
+    let analyzer = Analyzer::from_slice(&[2, 4, 2, 4, 2]);
+
+    assert_eq!(None, analyzer.find_pattern_set(1));
+    assert_eq!(Some(analyzer.pattern_set(2)), analyzer.find_pattern_set(4));
+    assert_eq!(analyzer.find_patterns(4), analyzer.find_pattern_set(4).unwrap().expand());
+}
+
+#[test]
+fn pattern_set_scales() {
+    // Each of the 20 positions can be described by 11 different operations (including the
+    // products of previous terms), so there are too many patterns to count.
+    let analyzer = Analyzer::from_slice(&[1; 41]);
+    let set = analyzer.pattern_set(20);
+
+    assert_eq!(11, set.position(19).len());
+    assert_eq!(usize::MAX, set.count());
+}
+
+#[test]
+fn find_best_pattern() {
+    let analyzer = Analyzer::from_slice(&[2, 4, 2, 4]);
+
+    assert_eq!(Some(pat![Square, SquareRoot]), analyzer.find_any_pattern(2));
+    assert_eq!(Some(pat![Plus(2), Plus(-2)]), analyzer.find_best_pattern(2));
+    assert_eq!(Some(pat![Plus(0)]), Analyzer::from_slice(&[1; 41]).find_best_pattern(1));
+    assert_eq!(None, Analyzer::from_slice(&[1, 2, 1, 3]).find_best_pattern(1));
+}
+
+#[test]
+fn find_ranked_patterns() {
+    let analyzer = Analyzer::from_slice(&[2, 4, 2, 4]);
+    let ranked = analyzer.find_ranked_patterns(2);
+
+    assert_eq!(16, ranked.len());
+    assert_eq!(pat![Plus(2), Plus(-2)], ranked[0]);
+    assert_eq!(pat![Const(4), Const(2)], ranked[15]);
+    assert!(ranked.windows(2).all(|pair| pair[0].cost() <= pair[1].cost()));
+}
+
+#[test]
+fn short_sequences() {
+    for slice in &[&[][..], &[5][..]] {
+        let analyzer = Analyzer::with_meta(slice);
+
+        assert_eq!(None, analyzer.find_any_pattern(3));
+        assert_eq!(None, analyzer.find_best_pattern(3));
+        assert_eq!(Vec::<Pattern>::new(), analyzer.find_patterns(3));
+        assert!(analyzer.pattern_set(1).is_empty());
+    }
+
+    assert_eq!(Some(pat![Plus(2)]), Analyzer::from_slice(&[5, 7]).find_best_pattern(1));
+    assert_eq!(Vec::<i32>::new(), pat![Plus(2)].predict_next(&[], 2));
+}
+
+#[test]
+fn anomalies() {
+    let pat = pat![Plus(3), Plus(-1)];
+    let slice = &[1, 4, 3, 6, 5, 9, 8, 11];
+
+    assert_eq!(
+        vec![Anomaly { index: 5, elem: Some(Plus(3)), expected: Some(8), actual: 9 }],
+        pat.anomalies(slice)
+    );
+    assert_eq!(pat.anomalies(slice).into_iter().next(), pat.first_anomaly(slice));
+    assert!(pat.anomalies(&[1, 4, 3, 6, 5]).is_empty());
+    assert_eq!(None, pat.first_anomaly(&[1]));
+    assert_eq!(
+        Some(Anomaly { index: 1, elem: None, expected: None, actual: 4 }),
+        Pattern::empty().first_anomaly(&[1, 4])
+    );
+}
+
+#[test]
+fn matches() {
+    let pat = pat![Plus(3), Plus(-1)];
+
+    assert!(pat.matches(&[1, 4, 3, 6, 5]));
+    assert!(pat.matches(&[7]));
+    assert!(pat.matches(&[]));
+    assert!(!pat.matches(&[1, 4, 3, 6, 6]));
+    assert_eq!(Some(4), pat.first_mismatch(&[1, 4, 3, 6, 6]));
+    assert_eq!(None, pat.first_mismatch(&[1, 4, 3, 6, 5]));
+    assert!(!Pattern::empty().matches(&[1, 4]));
+}
+
+#[test]
+fn recurrences() {
+    let fibonacci = &[0, 1, 1, 2, 3, 5, 8, 13];
+    let tribonacci = &[0, 0, 1, 1, 2, 4, 7, 13, 24];
+    let products = &[1, 2, 2, 4, 8, 32];
+
+    assert_eq!(Some(pat![Sum(2)]), Analyzer::from_slice(fibonacci).find_best_pattern(3));
+    assert_eq!(Some(pat![Sum(3)]), Analyzer::from_slice(tribonacci).find_best_pattern(3));
+    assert_eq!(Some(pat![Product(2)]), Analyzer::from_slice(products).find_best_pattern(3));
+    assert_eq!(vec![21, 34], pat![Sum(2)].predict_next(fibonacci, 2));
+    assert_eq!(Some(7), pat![Sum(2)].first_mismatch(&[0, 1, 1, 2, 3, 5, 8, 14]));
+}
+
+#[test]
+fn recurrences_need_enough_terms() {
+    // Any three terms could be the start of a sum of two.
+    assert!(!Analyzer::from_slice(&[4, 9, 13]).find_patterns_of_length(1).contains(&pat![Sum(2)]));
+    assert!(Analyzer::from_slice(&[4, 9, 13, 22]).find_patterns_of_length(1).contains(&pat![Sum(2)]));
+}
+
+#[test]
+fn weighted_sums() {
+    let pell = &[0, 1, 2, 5, 12, 29, 70, 169];
+    let options = AnalyzerBuilder::new().enable(Operation::Linear);
+    let pat = pat![Linear(LinearRecurrence { coefficients: vec![2, 1], constant: 0 })];
+
+    assert_eq!(None, Analyzer::from_slice(pell).find_best_pattern(3));
+    assert_eq!(Some(pat.clone()), options.build(pell).unwrap().find_best_pattern(3));
+    assert_eq!(vec![408, 985], pat.predict_next(pell, 2));
+
+    // Each of the two weights takes two terms to determine, plus two more to check them.
+    assert!(!options.build(&pell[..5]).unwrap().find_patterns_of_length(1).contains(&pat));
+    assert!(options.build(&pell[..6]).unwrap().find_patterns_of_length(1).contains(&pat));
+}
+
+#[test]
+fn linear_recurrence() {
+    let pell = &[0, 1, 2, 5, 12, 29, 70];
+    let analyzer = Analyzer::from_slice(pell);
+    let recurrence = analyzer.find_linear_recurrence(4).unwrap();
+
+    assert_eq!(None, analyzer.find_any_pattern(3));
+    assert_eq!(LinearRecurrence { coefficients: vec![2, 1], constant: 0 }, recurrence);
+    assert!(pat![Linear(recurrence.clone())].matches(pell));
+    assert_eq!(Some(6), pat![Linear(recurrence)].first_mismatch(&[0, 1, 2, 5, 12, 29, 71]));
+
+    // a(n) = 3 * a(n - 1) - 1
+    let recurrence = Analyzer::from_slice(&[1, 2, 5, 14, 41]).find_linear_recurrence(4).unwrap();
+    assert_eq!("linear [3] -1", format!("{}", recurrence));
+}
+
+#[test]
+fn index_formulas() {
+    let primes = &[2, 3, 5, 7, 11, 13, 17];
+    let formula = Analyzer::from_slice(primes).find_index_formula(3).unwrap();
+    let pat = pat![Index(formula.clone())];
+
+    assert_eq!(IndexFormula::Scaled { family: IndexFamily::Prime, scale: 1, start: 0, offset: 0 }, formula);
+    assert_eq!("a(n) = prime(n)", format!("{}", pat));
+    assert_eq!(vec![19, 23], pat.predict_next(primes, 2));
+    assert_eq!(vec![29, 31], pat.predict(23, 8, 2));
+    assert_eq!(Some(4), pat.first_mismatch(&[2, 3, 5, 7, 9]));
+
+    // The first term is computed from its index too.
+    let squares = pat![Index(IndexFormula::Polynomial(vec![0, 0, 1]))];
+    assert!(!squares.matches(&[5, 1, 4, 9]));
+    assert_eq!(Some(0), squares.first_mismatch(&[5, 1, 4, 9]));
+    assert_eq!(Some(0), squares.anomalies(&[5]).first().and_then(|anomaly| anomaly.expected));
+
+    // 2 * n^2 + 1, alternating with doubling the previous term.
+    let pat = pat![Index(IndexFormula::Polynomial(vec![1, 0, 2])), Mult(2)];
+    assert!(pat.matches(&[0, 3, 6, 19, 38, 51]));
+
+    let formula = Analyzer::from_slice(&[1, 2, 6, 24, 120]).find_index_formula(3).unwrap();
+    assert_eq!("a(n) = fact(n+1)", format!("{}", formula));
+    assert_eq!(Ok(Index(formula)), "a(n) = fact(n+1)".parse());
+
+    assert_eq!(None, Analyzer::from_slice(&[1, 2, 4, 7, 11, 17, 20, 30]).find_index_formula(3));
+}
+
+#[test]
+fn index_formulas_scale() {
+    // Each term is generated from the ones before it, rather than from scratch.
+    let pat = pat![Index(IndexFormula::Scaled { family: IndexFamily::Prime, scale: 1, start: 0, offset: 0 })];
+    let primes: Vec<i64> = (2..).filter(|&p| (2..p).take_while(|d| d * d <= p).all(|d| p % d != 0)).take(5000).collect();
+
+    assert!(pat.matches(&primes));
+    assert_eq!(vec![48619, 48623], pat.predict_next(&primes, 2));
+    assert_eq!(Some(IndexFormula::Scaled { family: IndexFamily::Prime, scale: 1, start: 0, offset: 0 }),
+               Analyzer::from_slice(&primes).find_index_formula(3));
+}
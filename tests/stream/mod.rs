@@ -0,0 +1,85 @@
+use sea_canal::{AnalyzerBuilder, AnalyzerError, Operation, OverflowPolicy, StreamingAnalyzer};
+use sea_canal::Pattern;
+use sea_canal::PatternElem::*;
+
+/// Pushes `slice` one term at a time, checking after each one that the streaming analyzer agrees
+/// with an analyzer given every term so far.
+fn assert_matches_batch(options: AnalyzerBuilder, slice: &[i32], max: usize) {
+    let mut stream = options.build_streaming(max);
+
+    for (i, &n) in slice.iter().enumerate() {
+        stream.push(n).unwrap();
+
+        // A single term is too short to build an analyzer for.
+        let batch = match options.build(&slice[..i + 1]) {
+            Ok(batch) => batch,
+            Err(_) => continue,
+        };
+
+        for n in 1..max + 2 {
+            assert_eq!(batch.pattern_set(n), stream.pattern_set(n), "{:?}, length {}", &slice[..i + 1], n);
+        }
+
+        assert_eq!(batch.find_best_pattern(max), stream.find_best_pattern(max));
+        assert_eq!(batch.find_any_pattern(max), stream.find_any_pattern(max));
+    }
+}
+
+#[test]
+fn matches_batch_analysis() {
+    assert_matches_batch(AnalyzerBuilder::new(), &[7, 1, 3, 9, 3, 5, 25, 19], 4);
+    assert_matches_batch(AnalyzerBuilder::new(), &[2, 4, 2, 4, 2], 3);
+    assert_matches_batch(AnalyzerBuilder::new(), &[1, 2, 6, 24, 120], 2);
+}
+
+#[test]
+fn unchanged_terms() {
+    // The moduli that leave a term unchanged grow with the largest term seen so far.
+    assert_matches_batch(AnalyzerBuilder::new(), &[3, 3, 5, 5, 9, 9, 40, 40], 3);
+    assert_matches_batch(AnalyzerBuilder::new(), &[1, 1, 1, 1, 10], 2);
+    assert_matches_batch(AnalyzerBuilder::new().max_modulus(6), &[3, 3, 5, 5, 9, 9], 2);
+}
+
+#[test]
+fn meta_patterns() {
+    assert_matches_batch(AnalyzerBuilder::new().meta_depth(1), &[1, 2, 4, 7, 11, 16], 2);
+}
+
+#[test]
+fn narrows_as_terms_arrive() {
+    let mut stream = StreamingAnalyzer::new(2);
+    assert!(stream.is_empty());
+    assert_eq!(None, stream.find_any_pattern(2));
+
+    stream.push(2).unwrap();
+    stream.push(4).unwrap();
+    assert!(stream.pattern_set(1).contains(&pat![Plus(2)]));
+    assert!(stream.pattern_set(1).contains(&pat![Mult(2)]));
+
+    stream.push(8).unwrap();
+    assert_eq!(vec![pat![Mult(2)]], stream.find_patterns(2));
+
+    stream.push(4).unwrap();
+    assert_eq!(4, stream.len());
+    assert_eq!(Vec::<Pattern>::new(), stream.find_patterns(1));
+    assert!(stream.find_patterns(2).contains(&pat![Const(4), Mult(2)]));
+}
+
+#[test]
+fn reject_overflow() {
+    let mut stream = AnalyzerBuilder::new().overflow(OverflowPolicy::Reject).build_streaming(2);
+
+    stream.push(i32::MIN).unwrap();
+    assert_eq!(Err(AnalyzerError::Overflow { index: 0 }), stream.push(i32::MAX));
+    assert_eq!(1, stream.len());
+
+    stream.push(i32::MIN + 1).unwrap();
+    assert_eq!(Some(pat![Plus(1)]), stream.find_best_pattern(2));
+}
+
+#[test]
+fn recurrences() {
+    assert_matches_batch(AnalyzerBuilder::new(), &[0, 0, 1, 1, 2, 4, 7, 13, 24], 3);
+    assert_matches_batch(AnalyzerBuilder::new(), &[2, 2, 4, 4, 16, 64], 2);
+    assert_matches_batch(AnalyzerBuilder::new().enable(Operation::Linear), &[0, 1, 2, 5, 12, 29, 70], 2);
+}